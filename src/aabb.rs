@@ -1,7 +1,12 @@
 use ray::Ray3;
+use stats;
 
-use cgmath::Point3;
+use cgmath::{
+    Point3,
+    EuclideanSpace,
+};
 
+use std::f32;
 use std::mem::swap;
 
 #[derive(Copy, Clone)]
@@ -25,7 +30,44 @@ impl AABB {
         )
     }
 
+    pub fn centroid(&self) -> Point3<f32> {
+        self.min.midpoint(self.max)
+    }
+
+    pub fn surface_area(&self) -> f32 {
+        let extent = self.max - self.min;
+        2f32 * (extent.x * extent.y + extent.y * extent.z + extent.z * extent.x)
+    }
+
+    /// Where a ray's line enters and leaves the box, each with the axis of the slab it crosses
+    /// there, or `None` if it passes the box by. Either may be behind the ray's origin.
+    pub fn entry_and_exit(&self, r: &Ray3<f32>) -> Option<((f32, usize), (f32, usize))> {
+        let mut entry = (f32::NEG_INFINITY, 0);
+        let mut exit = (f32::INFINITY, 0);
+        for a in 0..3 {
+            let inv_d = 1f32 / r.direction[a];
+            let mut t0 = (self.min[a] - r.origin[a]) * inv_d;
+            let mut t1 = (self.max[a] - r.origin[a]) * inv_d;
+
+            if inv_d < 0f32 {
+                swap(&mut t0, &mut t1)
+            }
+            if t0 > entry.0 {
+                entry = (t0, a);
+            }
+            if t1 < exit.0 {
+                exit = (t1, a);
+            }
+        }
+        if exit.0 < entry.0 {
+            None
+        } else {
+            Some((entry, exit))
+        }
+    }
+
     pub fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> bool {
+        stats::count_aabb_test();
         for a in 0..3 {
             let inv_d = 1f32 / r.direction[a];
             let mut t0 = (self.min[a] - r.origin[a]) * inv_d;
@@ -47,7 +89,8 @@ impl AABB {
                 t_max
             };
 
-            if t_max_2 <= t_min_2 {
+            // flat boxes (e.g. around axis-aligned quads) have t0 == t1 and must still be hit
+            if t_max_2 < t_min_2 {
                 return false;
             }
         }
@@ -0,0 +1,115 @@
+use checkpoint::Accumulation;
+use output::{
+    self,
+    Format,
+    RadianceImage,
+    ToneMap,
+};
+
+use image::{
+    Rgb,
+    RgbImage,
+};
+
+use std::io::{
+    self,
+    Write,
+};
+
+/// Stops sampling pixels once the standard error of their mean luminance falls below
+/// `threshold` relative to that mean, after taking at least `min_samples`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct AdaptiveSampling {
+    pub threshold: f32,
+    pub min_samples: u32,
+}
+
+/// How many samples each pixel should take in the next pass, in rows. Every pixel takes up to
+/// `pass_samples` until it has `max_samples` or, when sampling adaptively, has converged, so a
+/// plan of all zeros means the render is finished.
+pub fn plan(accumulation: &Accumulation, pass_samples: u32, max_samples: u32, adaptive: Option<&AdaptiveSampling>) -> Vec<u32> {
+    let width = accumulation.sums.width();
+    accumulation.samples.iter().enumerate().map(|(index, &samples)| {
+        let remaining = max_samples.saturating_sub(samples);
+        match adaptive {
+            Some(adaptive) if samples < adaptive.min_samples => pass_samples.max(adaptive.min_samples - samples).min(remaining),
+            Some(adaptive) => {
+                let (x, y) = (index as u32 % width, index as u32 / width);
+                if accumulation.relative_error(x, y) < adaptive.threshold {
+                    0
+                } else {
+                    pass_samples.min(remaining)
+                }
+            }
+            None => pass_samples.min(remaining),
+        }
+    }).collect()
+}
+
+// from black through blue, red and yellow to white as more samples are taken
+fn heat_colour(t: f32) -> [u8; 3] {
+    let stops = [[0f32, 0f32, 0f32], [0f32, 0f32, 255f32], [255f32, 0f32, 0f32], [255f32, 255f32, 0f32], [255f32, 255f32, 255f32]];
+    let position = t.max(0f32).min(1f32) * (stops.len() - 1) as f32;
+    let index = (position as usize).min(stops.len() - 2);
+    let f = position - index as f32;
+    let (a, b) = (stops[index], stops[index + 1]);
+    [
+        (a[0] + f * (b[0] - a[0])) as u8,
+        (a[1] + f * (b[1] - a[1])) as u8,
+        (a[2] + f * (b[2] - a[2])) as u8,
+    ]
+}
+
+/// Writes the number of samples taken for each pixel, as raw counts in high dynamic range
+/// formats or as a heatmap scaled to `max_samples` otherwise.
+pub fn write_heatmap<W: Write>(accumulation: &Accumulation, max_samples: u32, format: Format, writer: &mut W) -> io::Result<()> {
+    let (width, height) = accumulation.sums.dimensions();
+    let count = |x: u32, y: u32| accumulation.samples[(y * width + x) as usize] as f32;
+    if format.is_high_dynamic_range() {
+        let counts = RadianceImage::from_fn(width, height, |x, y| Rgb { data: [count(x, y); 3] });
+        output::write(&counts, format, &ToneMap::default(), writer)
+    } else {
+        let heatmap = RgbImage::from_fn(width, height, |x, y| Rgb { data: heat_colour(count(x, y) / max_samples.max(1) as f32) });
+        output::write_u8(&heatmap, format, writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cgmath::Vector3;
+
+    // a flat pixel next to a noisy one, each with `samples` samples
+    fn flat_and_noisy(samples: u32) -> Accumulation {
+        let mut accumulation = Accumulation::new(2, 1);
+        for i in 0..samples {
+            let value = if i % 2 == 0 { 0.1 } else { 1.9 };
+            accumulation.add_sample(0, 0, Vector3::new(1.0, 1.0, 1.0));
+            accumulation.add_sample(1, 0, Vector3::new(value, value, value));
+        }
+        accumulation
+    }
+
+    #[test]
+    fn uniform_without_adaptive_sampling() {
+        assert_eq!(plan(&flat_and_noisy(0), 16, 40, None), vec![16, 16]);
+        assert_eq!(plan(&flat_and_noisy(32), 16, 40, None), vec![8, 8]);
+        assert_eq!(plan(&flat_and_noisy(40), 16, 40, None), vec![0, 0]);
+    }
+
+    #[test]
+    fn stops_converged_pixels_after_the_minimum() {
+        let adaptive = AdaptiveSampling { threshold: 0.05, min_samples: 24 };
+        assert_eq!(plan(&flat_and_noisy(0), 16, 1000, Some(&adaptive)), vec![24, 24]);
+        assert_eq!(plan(&flat_and_noisy(24), 16, 1000, Some(&adaptive)), vec![0, 16]);
+        assert_eq!(plan(&flat_and_noisy(24), 16, 30, Some(&adaptive)), vec![0, 6]);
+    }
+
+    #[test]
+    fn heatmap_runs_from_black_to_white() {
+        assert_eq!(heat_colour(0.0), [0, 0, 0]);
+        assert_eq!(heat_colour(0.5), [255, 0, 0]);
+        assert_eq!(heat_colour(1.0), [255, 255, 255]);
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::str::FromStr;
+
+/// How a keyframed value eases from one keyframe towards the next.
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    // holds the keyframe's value until the next keyframe
+    Step,
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        Curve::Linear
+    }
+}
+
+impl Curve {
+    /// How far the value has gone from one keyframe to the next when `amount` of the time
+    /// between them has passed.
+    pub fn apply(&self, amount: f32) -> f32 {
+        match *self {
+            Curve::Linear => amount,
+            Curve::EaseIn => amount * amount,
+            Curve::EaseOut => amount * (2f32 - amount),
+            Curve::EaseInOut => amount * amount * (3f32 - 2f32 * amount),
+            Curve::Step => if amount < 1f32 { 0f32 } else { 1f32 },
+        }
+    }
+}
+
+/// The frames of an animation from `start` to `end` inclusive, written `START..END`. Frame `n`
+/// is rendered at `n / fps` seconds of scene time.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FrameRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl FrameRange {
+    pub fn frames(&self) -> ::std::ops::RangeInclusive<u32> {
+        self.start..=self.end
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct FrameRangeError(String);
+
+impl fmt::Display for FrameRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for FrameRange {
+    type Err = FrameRangeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ends = s.splitn(2, "..");
+        let (start, end) = match (ends.next(), ends.next()) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Err(FrameRangeError("expected frames as START..END".to_string())),
+        };
+        let parse_frame = |frame: &str| frame.trim().parse::<u32>().map_err(|err| FrameRangeError(format!("{}: {}", frame, err)));
+        let (start, end) = (parse_frame(start)?, parse_frame(end)?);
+        if end < start {
+            return Err(FrameRangeError("the last frame comes before the first".to_string()));
+        }
+        Ok(FrameRange { start, end })
+    }
+}
+
+/// Where a frame is written, numbering the output path so that `out.png` becomes
+/// `out_0001.png` for frame 1.
+pub fn frame_path(path: &Path, frame: u32) -> PathBuf {
+    let stem = path.file_stem().map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+    let numbered = path.with_file_name(format!("{}_{:04}", stem, frame));
+    match path.extension() {
+        Some(extension) => numbered.with_extension(extension),
+        None => numbered,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_start_and_end_with_the_keyframes() {
+        for &curve in [Curve::Linear, Curve::EaseIn, Curve::EaseOut, Curve::EaseInOut].iter() {
+            assert_eq!((curve.apply(0.0), curve.apply(1.0)), (0.0, 1.0), "{:?}", curve);
+        }
+        assert!(Curve::EaseIn.apply(0.5) < 0.5 && Curve::EaseOut.apply(0.5) > 0.5);
+        assert_eq!(Curve::EaseInOut.apply(0.5), 0.5);
+        assert_eq!((Curve::Step.apply(0.99), Curve::Step.apply(1.0)), (0.0, 1.0));
+    }
+
+    #[test]
+    fn parses_inclusive_frame_ranges() {
+        let range: FrameRange = "1..48".parse().unwrap();
+        assert_eq!(range, FrameRange { start: 1, end: 48 });
+        assert_eq!(range.frames().count(), 48);
+        let last: FrameRange = format!("{}..{}", u32::MAX - 1, u32::MAX).parse().unwrap();
+        assert_eq!(last.frames().collect::<Vec<u32>>(), vec![u32::MAX - 1, u32::MAX]);
+        assert!("48..1".parse::<FrameRange>().is_err());
+        assert!("12".parse::<FrameRange>().is_err());
+        assert!("a..b".parse::<FrameRange>().is_err());
+    }
+
+    #[test]
+    fn numbers_frame_paths() {
+        assert_eq!(frame_path(Path::new("renders/out.png"), 1), PathBuf::from("renders/out_0001.png"));
+        assert_eq!(frame_path(Path::new("out"), 120), PathBuf::from("out_0120"));
+    }
+}
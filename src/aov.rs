@@ -0,0 +1,298 @@
+use hitable::HitRecord;
+use ray::Ray3;
+use material;
+use output::{
+    self,
+    Format,
+    RadianceImage,
+    ToneMap,
+};
+
+use cgmath::{
+    Vector3,
+    InnerSpace,
+    Zero,
+};
+use image::{
+    ImageBuffer,
+    Rgb,
+    RgbImage,
+};
+
+use std::io::{
+    self,
+    Write,
+};
+
+/// An auxiliary render pass, describing the first surface seen through each pixel.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Aov {
+    Depth,
+    Normal,
+    Albedo,
+    Id,
+}
+
+impl Aov {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "depth" => Some(Aov::Depth),
+            "normal" => Some(Aov::Normal),
+            "albedo" => Some(Aov::Albedo),
+            "id" => Some(Aov::Id),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Aov::Depth => "depth",
+            Aov::Normal => "normal",
+            Aov::Albedo => "albedo",
+            Aov::Id => "id",
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+struct AovPixel {
+    samples: u32,
+    hits: u32,
+    distance: f32,
+    normal: Vector3<f32>,
+    albedo: Vector3<f32>,
+    id: u32,
+}
+
+impl AovPixel {
+    fn new() -> Self {
+        AovPixel {
+            samples: 0,
+            hits: 0,
+            distance: 0f32,
+            normal: Vector3::zero(),
+            albedo: Vector3::zero(),
+            id: 0,
+        }
+    }
+
+    fn add_sample(&mut self, r: &Ray3<f32>, hit: Option<&HitRecord>) {
+        self.samples += 1;
+        if let Some(rec) = hit {
+            // ids cannot be averaged, so the first object hit labels the pixel
+            if self.hits == 0 {
+                self.id = rec.id;
+            }
+            self.hits += 1;
+            self.distance += rec.t * r.direction.magnitude();
+            self.normal += rec.normal.normalize();
+            if let Some(ref hit_material) = rec.material {
+                self.albedo += material::albedo(hit_material, rec);
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &AovPixel) {
+        if self.hits == 0 {
+            self.id = other.id;
+        }
+        self.samples += other.samples;
+        self.hits += other.hits;
+        self.distance += other.distance;
+        self.normal += other.normal;
+        self.albedo += other.albedo;
+    }
+
+    // depth is averaged over the samples which hit something and is zero where nothing was hit,
+    // whereas normals and albedo fade towards zero at the edges of objects
+    fn value(&self, aov: Aov) -> [f32; 3] {
+        let mean = |sum: f32, count: u32| if count == 0 { 0f32 } else { sum / count as f32 };
+        match aov {
+            Aov::Depth => {
+                let depth = mean(self.distance, self.hits);
+                [depth, depth, depth]
+            }
+            Aov::Normal => [mean(self.normal.x, self.samples), mean(self.normal.y, self.samples), mean(self.normal.z, self.samples)],
+            Aov::Albedo => [mean(self.albedo.x, self.samples), mean(self.albedo.y, self.samples), mean(self.albedo.z, self.samples)],
+            Aov::Id => [self.id as f32, self.id as f32, self.id as f32],
+        }
+    }
+}
+
+/// Accumulates the requested passes for a block of pixels. Nothing is stored when no passes
+/// were requested.
+pub struct AovBuffer {
+    aovs: Vec<Aov>,
+    width: u32,
+    height: u32,
+    pixels: Vec<AovPixel>,
+}
+
+impl AovBuffer {
+    pub fn new(aovs: &[Aov], width: u32, height: u32) -> Self {
+        let size = if aovs.is_empty() { 0 } else { (width * height) as usize };
+        AovBuffer {
+            aovs: aovs.to_vec(),
+            width,
+            height,
+            pixels: vec![AovPixel::new(); size],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aovs.is_empty()
+    }
+
+    /// Records what the camera ray `r` for pixel (x, y) hit first, if it hit anything.
+    pub fn add_sample(&mut self, x: u32, y: u32, r: &Ray3<f32>, hit: Option<&HitRecord>) {
+        if !self.is_empty() {
+            self.pixels[(y * self.width + x) as usize].add_sample(r, hit);
+        }
+    }
+
+    /// Adds the samples from a smaller buffer, such as one for a tile, to this one with its top
+    /// left at (x, y).
+    pub fn add_from(&mut self, other: &AovBuffer, x: u32, y: u32) {
+        if self.is_empty() {
+            return;
+        }
+        for row in 0..other.height {
+            for column in 0..other.width {
+                let index = ((y + row) * self.width + x + column) as usize;
+                self.pixels[index].merge(&other.pixels[(row * other.width + column) as usize]);
+            }
+        }
+    }
+
+    pub fn images(&self) -> Vec<(Aov, RadianceImage)> {
+        self.aovs.iter().map(|&aov| {
+            let img = ImageBuffer::from_fn(self.width, self.height, |x, y| {
+                Rgb { data: self.pixels[(y * self.width + x) as usize].value(aov) }
+            });
+            (aov, img)
+        }).collect()
+    }
+}
+
+// a stable, well spread colour for each id so that neighbouring objects are easy to tell apart
+fn id_colour(id: u32) -> [u8; 3] {
+    if id == 0 {
+        return [0, 0, 0];
+    }
+    let hash = id.wrapping_mul(2654435761);
+    [(hash >> 24) as u8, (hash >> 16) as u8, (hash >> 8) as u8]
+}
+
+// 8-bit formats cannot hold raw depths, normals or ids, so each pass is mapped into a viewable range
+fn to_display(aov: Aov, img: &RadianceImage) -> RgbImage {
+    let max_depth = img.pixels().fold(0f32, |max, pixel| max.max(pixel.data[0]));
+    let tone_map = ToneMap::default();
+    ImageBuffer::from_fn(img.width(), img.height(), |x, y| {
+        let c = img.get_pixel(x, y).data;
+        Rgb {
+            data: match aov {
+                // nearer surfaces are brighter, and the background is black
+                Aov::Depth => {
+                    let grey = if c[0] > 0f32 { (255.99 * (1f32 - 0.9 * c[0] / max_depth)) as u8 } else { 0 };
+                    [grey, grey, grey]
+                }
+                Aov::Normal => [
+                    (255.99 * (0.5 * c[0] + 0.5)) as u8,
+                    (255.99 * (0.5 * c[1] + 0.5)) as u8,
+                    (255.99 * (0.5 * c[2] + 0.5)) as u8,
+                ],
+                Aov::Albedo => [tone_map.to_u8(c[0]), tone_map.to_u8(c[1]), tone_map.to_u8(c[2])],
+                Aov::Id => id_colour(c[0] as u32),
+            },
+        }
+    })
+}
+
+/// Writes a pass as raw values in high dynamic range formats, or as a viewable image otherwise.
+pub fn write<W: Write>(aov: Aov, img: &RadianceImage, format: Format, writer: &mut W) -> io::Result<()> {
+    if format.is_high_dynamic_range() {
+        output::write(img, format, &ToneMap::default(), writer)
+    } else {
+        output::write_u8(&to_display(aov, img), format, writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hitable::{
+        Hitable,
+        HitableList,
+        Identified,
+    };
+    use sphere::Sphere;
+    use material::Material;
+
+    use cgmath::Point3;
+
+    use std::f32;
+
+    // traces a camera ray from the origin into the buffer
+    fn sample(buffer: &mut AovBuffer, x: u32, y: u32, direction: Vector3<f32>, world: &Hitable) {
+        let r = Ray3::new(Point3::new(0.0, 0.0, 0.0), direction, 0.0);
+        buffer.add_sample(x, y, &r, world.hit(&r, 0.001, f32::MAX).as_ref());
+    }
+
+    fn red_sphere() -> HitableList {
+        HitableList::new().with_hitable(Sphere::new(Point3::new(0.0, 0.0, -5.0), 1.0, Material::Lambertian { albedo: Vector3::new(0.8, 0.1, 0.1) }))
+    }
+
+    #[test]
+    fn records_the_first_hit() {
+        let world = red_sphere();
+        let mut buffer = AovBuffer::new(&[Aov::Depth, Aov::Normal, Aov::Albedo, Aov::Id], 2, 1);
+        // the direction is deliberately not a unit vector, depth is still a distance
+        sample(&mut buffer, 0, 0, Vector3::new(0.0, 0.0, -2.0), &world);
+        sample(&mut buffer, 1, 0, Vector3::new(0.0, 1.0, 0.0), &world);
+
+        let images = buffer.images();
+        assert_eq!(images[0].1.get_pixel(0, 0).data, [4.0, 4.0, 4.0]);
+        assert_eq!(images[1].1.get_pixel(0, 0).data, [0.0, 0.0, 1.0]);
+        assert_eq!(images[2].1.get_pixel(0, 0).data, [0.8, 0.1, 0.1]);
+        assert_eq!(images[2].1.get_pixel(1, 0).data, [0.0, 0.0, 0.0]);
+        assert_eq!(images[3].1.get_pixel(1, 0).data, [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn normals_and_albedo_are_averaged_over_all_samples() {
+        let world = red_sphere();
+        let mut buffer = AovBuffer::new(&[Aov::Depth, Aov::Albedo], 1, 1);
+        sample(&mut buffer, 0, 0, Vector3::new(0.0, 0.0, -1.0), &world);
+        sample(&mut buffer, 0, 0, Vector3::new(0.0, 0.0, 1.0), &world);
+
+        let images = buffer.images();
+        assert_eq!(images[0].1.get_pixel(0, 0).data, [4.0, 4.0, 4.0]);
+        assert_eq!(images[1].1.get_pixel(0, 0).data, [0.4, 0.05, 0.05]);
+    }
+
+    #[test]
+    fn adds_tiles_in_place() {
+        let world = HitableList::new().with_hitable(Identified::new(1, Box::new(red_sphere())));
+        let mut tile = AovBuffer::new(&[Aov::Id, Aov::Albedo], 2, 2);
+        sample(&mut tile, 1, 1, Vector3::new(0.0, 0.0, -1.0), &world);
+
+        let mut buffer = AovBuffer::new(&[Aov::Id, Aov::Albedo], 4, 4);
+        buffer.add_from(&tile, 2, 1);
+        let mut miss = AovBuffer::new(&[Aov::Id, Aov::Albedo], 1, 1);
+        sample(&mut miss, 0, 0, Vector3::new(0.0, 0.0, 1.0), &world);
+        buffer.add_from(&miss, 3, 2);
+
+        let images = buffer.images();
+        assert_eq!(images[0].1.get_pixel(3, 2).data, [1.0, 1.0, 1.0]);
+        assert_eq!(images[0].1.get_pixel(2, 1).data, [0.0, 0.0, 0.0]);
+        assert_eq!(images[1].1.get_pixel(3, 2).data, [0.4, 0.05, 0.05]);
+    }
+
+    #[test]
+    fn nothing_is_stored_without_passes() {
+        let mut buffer = AovBuffer::new(&[], 4, 4);
+        sample(&mut buffer, 0, 0, Vector3::new(0.0, 0.0, -1.0), &red_sphere());
+        assert!(buffer.pixels.is_empty());
+        assert!(buffer.images().is_empty());
+    }
+}
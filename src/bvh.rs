@@ -4,93 +4,172 @@ use hitable::{
 };
 use ray::Ray3;
 use aabb::AABB;
-use randomutil::seedable_unit_distribution;
+use stats;
 
-use cgmath::Point3;
-
-use std::time::Instant;
+use std::f32;
 use std::sync::Arc;
 use std::cmp::Ordering;
 
+type ArcHitable = Arc<Hitable + Send + Sync>;
+
 pub struct BVHNode {
-    left: Arc<Hitable + Send + Sync>,
-    right: Arc<Hitable + Send + Sync>,
+    left: ArcHitable,
+    right: Option<ArcHitable>,
     bounding_box: AABB,
 }
 
-type ArcHitable = Arc<Hitable + Send + Sync>;
+fn centroid_axis_compare(axis: usize) -> Box<Fn(&(AABB, ArcHitable), &(AABB, ArcHitable)) -> Ordering> {
+    Box::new(move |a: &(AABB, ArcHitable), b: &(AABB, ArcHitable)| {
+        a.0.centroid()[axis].partial_cmp(&b.0.centroid()[axis]).unwrap_or(Ordering::Equal)
+    })
+}
 
-fn box_axis_compare(axis: usize) -> Box<Fn(&ArcHitable, &ArcHitable) -> Ordering> {
-    return Box::new(move |a: &ArcHitable, b: &ArcHitable| {
-        a.bounding_box(Instant::now(), Instant::now()).and_then(|box_left| {
-            b.bounding_box(Instant::now(), Instant::now()).map(|box_right| {
-                if box_left.min[axis] - box_right.min[axis] < 0f32 {
-                    Ordering::Less
-                } else {
-                    Ordering::Greater
-                }
-            })
-        }).unwrap_or_else(|| {
-            panic!("no bounding box in bvh node constructor");
-        })
-    });
+fn enclosing_box(entries: &[(AABB, ArcHitable)]) -> AABB {
+    entries[1..].iter().fold(entries[0].0, |acc, next| acc.surrounding_box(&next.0))
+}
+
+// surface area heuristic: the cost of splitting sorted entries after each index
+// is proportional to area(left) * count(left) + area(right) * count(right)
+fn sah_split(entries: &[(AABB, ArcHitable)]) -> (usize, f32) {
+    let n = entries.len();
+
+    let mut right_areas = vec![0f32; n];
+    let mut right_box = entries[n - 1].0;
+    for i in (1..n).rev() {
+        right_box = right_box.surrounding_box(&entries[i].0);
+        right_areas[i] = right_box.surface_area();
+    }
+
+    let mut best = (1, f32::MAX);
+    let mut left_box = entries[0].0;
+    for i in 1..n {
+        let cost = left_box.surface_area() * i as f32 + right_areas[i] * (n - i) as f32;
+        if cost < best.1 {
+            best = (i, cost);
+        }
+        left_box = left_box.surrounding_box(&entries[i].0);
+    }
+    best
 }
 
 impl BVHNode {
-    pub fn new(mut l: Vec<ArcHitable>, t0: Instant, t1: Instant) -> Self {
-        let axis = (3f32 * seedable_unit_distribution()) as usize;
-        if axis > 2 {
-            panic!("WTF")
+    pub fn new(l: Vec<ArcHitable>, t0: f32, t1: f32) -> Self {
+        let entries = l.into_iter().map(|hitable| {
+            let bounding_box = hitable.bounding_box(t0, t1).unwrap_or_else(|| {
+                panic!("no bounding box in bvh node constructor");
+            });
+            (bounding_box, hitable)
+        }).collect();
+
+        Self::build(entries)
+    }
+
+    fn build(mut entries: Vec<(AABB, ArcHitable)>) -> Self {
+        if entries.is_empty() {
+            panic!("no hitables in bvh node constructor");
         }
-        l.sort_by(&*box_axis_compare(axis));
 
-        let (left, right): (ArcHitable, ArcHitable) = if l.len() == 1 {
-            (l[0].clone(), l[0].clone())
-        } else if l.len() == 2 {
-            (l[0].clone(), l[1].clone())
-        } else {
-            // TODO: this is possibly bullshit
-            (
-                Arc::new(BVHNode::new(l[0..l.len() / 2].to_vec(), t0, t1)),
-                Arc::new(BVHNode::new(l[l.len() / 2 + 1..].to_vec(), t0, t1))
-            )
-        };
+        let bounding_box = enclosing_box(&entries);
+
+        if entries.len() == 1 {
+            return Self {
+                left: entries.pop().unwrap().1,
+                right: None,
+                bounding_box,
+            };
+        }
+
+        let mut best = (0, 1, f32::MAX);
+        for axis in 0..3 {
+            entries.sort_by(&*centroid_axis_compare(axis));
+            let (split, cost) = sah_split(&entries);
+            if cost < best.2 {
+                best = (axis, split, cost);
+            }
+        }
+
+        let (axis, split, _) = best;
+        entries.sort_by(&*centroid_axis_compare(axis));
+        let right_entries = entries.split_off(split);
 
         Self {
-            left: left,
-            right: right,
-            bounding_box: AABB::new(Point3::new(0f32, 0f32, 0f32), Point3::new(1f32, 1f32, 1f32))
+            left: Self::child(entries),
+            right: Some(Self::child(right_entries)),
+            bounding_box,
+        }
+    }
+
+    fn child(mut entries: Vec<(AABB, ArcHitable)>) -> ArcHitable {
+        if entries.len() == 1 {
+            entries.pop().unwrap().1
+        } else {
+            Arc::new(Self::build(entries))
         }
     }
 }
 
 impl Hitable for BVHNode {
     fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        stats::count_bvh_node();
         if self.bounding_box.hit(r, t_min, t_max) {
             let hit_left = self.left.hit(r, t_min, t_max);
-            let hit_right = self.right.hit(r, t_min, t_max);
-
-            if hit_left.is_some() && hit_right.is_some() {
-                let left_rec = hit_left.unwrap();
-                let right_rec = hit_right.unwrap();
-                if left_rec.t < right_rec.t {
-                    Some(left_rec)
-                } else {
-                    Some(right_rec)
-                }
-            } else if hit_left.is_some() {
-                hit_left
-            } else if hit_right.is_some() {
-                hit_right
-            } else {
-                None
-            }
+            let closest_so_far = hit_left.as_ref().map_or(t_max, |rec| rec.t);
+            let hit_right = self.right.as_ref().and_then(|right| right.hit(r, t_min, closest_so_far));
+
+            hit_right.or(hit_left)
         } else {
             None
         }
     }
 
-    fn bounding_box(&self, _: Instant, _: Instant) -> Option<AABB> {
-        unimplemented!()
+    fn bounding_box(&self, _: f32, _: f32) -> Option<AABB> {
+        Some(self.bounding_box)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hitable::HitableList;
+    use sphere::Sphere;
+    use material::Material;
+
+    use cgmath::{
+        Point3,
+        Vector3,
+    };
+
+    fn sphere(i: usize) -> Sphere {
+        Sphere::new(Point3::new(i as f32 * 3.0, 0.0, 0.0), 1.0, Material::Lambertian { albedo: Vector3::new(0.5, 0.5, 0.5) })
     }
-}
\ No newline at end of file
+
+    fn spheres() -> Vec<ArcHitable> {
+        (0..5).map(|i| Arc::new(sphere(i)) as ArcHitable).collect()
+    }
+
+    #[test]
+    fn bounding_box_surrounds_all_hitables() {
+        let bvh = BVHNode::new(spheres(), 0.0, 0.0);
+        let bounding_box = bvh.bounding_box(0.0, 0.0).unwrap();
+        assert_eq!(bounding_box.min, Point3::new(-1.0, -1.0, -1.0));
+        assert_eq!(bounding_box.max, Point3::new(13.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn hits_every_hitable() {
+        let bvh = BVHNode::new(spheres(), 0.0, 0.0);
+        for i in 0..5 {
+            let r = Ray3::new(Point3::new(i as f32 * 3.0, 10.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 0.0);
+            let rec = bvh.hit(&r, 0.001, f32::MAX);
+            assert_eq!(rec.map(|rec| rec.p), Some(Point3::new(i as f32 * 3.0, 1.0, 0.0)));
+        }
+    }
+
+    #[test]
+    fn hit_matches_hitable_list() {
+        let bvh = BVHNode::new(spheres(), 0.0, 0.0);
+        let list = (0..5).fold(HitableList::new(), |list, i| list.with_hitable(sphere(i)));
+        let r = Ray3::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), 0.0);
+        assert_eq!(bvh.hit(&r, 0.001, f32::MAX).map(|rec| rec.t), list.hit(&r, 0.001, f32::MAX).map(|rec| rec.t));
+    }
+}
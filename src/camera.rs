@@ -1,8 +1,6 @@
 use ray::Ray3;
-use randomutil::{
-    seedable_unit_distribution,
-    random_in_unit_disk,
-};
+use randomutil::random_in_unit_disk;
+use sampler::Sampler;
 
 use cgmath::{
     Point3,
@@ -11,11 +9,6 @@ use cgmath::{
     InnerSpace,
 };
 use std::f32;
-use std::time::{
-    Duration,
-    Instant,
-};
-use timeutil::TimeUtil;
 
 #[derive(Copy, Clone)]
 pub struct Camera {
@@ -26,8 +19,8 @@ pub struct Camera {
     lens_radius: f32,
     u: Vector3<f32>,
     v: Vector3<f32>,
-    aperture_open_time: Instant,
-    aperture_duration: Duration,
+    shutter_open: f32,
+    shutter_close: f32,
 }
 
 impl Camera {
@@ -38,8 +31,8 @@ impl Camera {
                aspect: f32,
                aperture: f32,
                focus_dist: f32,
-               aperture_open_time: Instant,
-               aperture_duration: Duration) -> Self {
+               shutter_open: f32,
+               shutter_close: f32) -> Self {
         let lens_radius = aperture / 2.0;
         let theta = vfov * f32::consts::PI / 180.0;
         let half_height = (theta / 2.0).tan();
@@ -57,15 +50,20 @@ impl Camera {
             lens_radius,
             u,
             v,
-            aperture_open_time,
-            aperture_duration,
+            shutter_open,
+            shutter_close,
         }
     }
 
-    pub fn get_ray(&self, s: f32, t: f32) -> Ray3<f32> {
-        let rd: Vector3<f32> = self.lens_radius * random_in_unit_disk();
+    /// The scene times, in seconds, between which rays are sent out.
+    pub fn shutter_interval(&self) -> (f32, f32) {
+        (self.shutter_open, self.shutter_close)
+    }
+
+    pub fn get_ray(&self, s: f32, t: f32, sampler: &mut Sampler) -> Ray3<f32> {
+        let rd: Vector3<f32> = self.lens_radius * random_in_unit_disk(sampler);
         let offset: Vector3<f32> = self.u * rd.x + self.v * rd.y;
-        let time = self.aperture_open_time + self.aperture_duration.mul_decimal(seedable_unit_distribution());
+        let time = self.shutter_open + (self.shutter_close - self.shutter_open) * sampler.next_1d();
         Ray3::new(self.origin + offset,
                   self.lower_left_corner + s * self.horizontal + t * self.vertical - self.origin.to_vec() - offset,
                   time)
@@ -0,0 +1,359 @@
+use film::Film;
+use filter::{
+    Filter,
+    FilterKind,
+};
+use sampler::SamplerKind;
+use output::{
+    luminance,
+    RadianceImage,
+};
+
+use cgmath::Vector3;
+
+use std::{
+    fmt,
+    io,
+};
+use std::io::{
+    Read,
+    Write,
+};
+use std::f32;
+use std::fs::File;
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"RTCHECK3";
+// checkpoints from before samples were filtered onto a separate film, and from before the
+// sampler and filter were recorded
+const OLDER_MAGICS: [&[u8; 8]; 2] = [b"RTCHECK1", b"RTCHECK2"];
+
+// numbered by their position, as they are written to checkpoints
+const SAMPLERS: [SamplerKind; 4] = [SamplerKind::Random, SamplerKind::Stratified, SamplerKind::Halton, SamplerKind::Sobol];
+const FILTERS: [FilterKind; 5] = [FilterKind::Box, FilterKind::Tent, FilterKind::Gaussian, FilterKind::Mitchell, FilterKind::Lanczos];
+
+/// The running sums of the samples taken within each pixel, along with how many were taken,
+/// from which adaptive sampling judges how noisy each pixel still is.
+#[derive(Clone)]
+pub struct Accumulation {
+    pub sums: RadianceImage,
+    // of each sample's luminance, for estimating how noisy each pixel still is
+    pub sums_of_squares: Vec<f32>,
+    pub samples: Vec<u32>,
+}
+
+impl Accumulation {
+    pub fn new(width: u32, height: u32) -> Self {
+        Accumulation {
+            sums: RadianceImage::new(width, height),
+            sums_of_squares: vec![0f32; (width * height) as usize],
+            samples: vec![0; (width * height) as usize],
+        }
+    }
+
+    pub fn add_sample(&mut self, x: u32, y: u32, colour: Vector3<f32>) {
+        let total = self.sums.get_pixel_mut(x, y);
+        total.data[0] += colour.x;
+        total.data[1] += colour.y;
+        total.data[2] += colour.z;
+        let index = (y * self.sums.width() + x) as usize;
+        self.sums_of_squares[index] += luminance(colour) * luminance(colour);
+        self.samples[index] += 1;
+    }
+
+    /// Adds the samples from a smaller accumulation, such as one for a tile, to this one with its
+    /// top left at (x, y).
+    pub fn add_from(&mut self, other: &Accumulation, x: u32, y: u32) {
+        for (other_x, other_y, pixel) in other.sums.enumerate_pixels() {
+            let total = self.sums.get_pixel_mut(x + other_x, y + other_y);
+            for (total_channel, channel) in total.data.iter_mut().zip(pixel.data.iter()) {
+                *total_channel += *channel;
+            }
+            let index = ((y + other_y) * self.sums.width() + x + other_x) as usize;
+            let other_index = (other_y * other.sums.width() + other_x) as usize;
+            self.sums_of_squares[index] += other.sums_of_squares[other_index];
+            self.samples[index] += other.samples[other_index];
+        }
+    }
+
+    /// The standard error of the mean luminance of pixel (x, y) relative to that mean, which is
+    /// infinite until there are enough samples to estimate it.
+    pub fn relative_error(&self, x: u32, y: u32) -> f32 {
+        let index = (y * self.sums.width() + x) as usize;
+        let samples = self.samples[index];
+        if samples < 2 {
+            return f32::INFINITY;
+        }
+        let n = samples as f32;
+        let sum = self.sums.get_pixel(x, y).data;
+        let mean = luminance(Vector3::new(sum[0], sum[1], sum[2])) / n;
+        let variance = ((self.sums_of_squares[index] / n - mean * mean) * n / (n - 1f32)).max(0f32);
+        // very dark pixels are compared against a floor so they are not sampled forever
+        (variance / n).sqrt() / mean.max(0.01)
+    }
+
+    pub fn mean_samples(&self) -> f32 {
+        self.samples.iter().map(|&samples| samples as f32).sum::<f32>() / self.samples.len().max(1) as f32
+    }
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Invalid(String),
+    Mismatch(String),
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CheckpointError::Io(ref err) => write!(f, "could not read checkpoint: {}", err),
+            CheckpointError::Invalid(ref message) => write!(f, "invalid checkpoint: {}", message),
+            CheckpointError::Mismatch(ref message) => write!(f, "checkpoint is for a different render: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointError {
+    fn from(err: io::Error) -> Self {
+        CheckpointError::Io(err)
+    }
+}
+
+/// An interrupted render, identified by the scene, seed, sampler, filter and resolutions it was
+/// started with.
+pub struct Checkpoint {
+    pub scene: String,
+    pub seed: u64,
+    pub sampler: SamplerKind,
+    pub filter: Filter,
+    pub accumulation: Accumulation,
+    pub film: Film,
+}
+
+fn write_u32<W: Write>(writer: &mut W, value: u32) -> io::Result<()> {
+    writer.write_all(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24)
+}
+
+// far beyond any render's resolution, so that a corrupt header is refused rather than allocated
+const MAX_PIXELS: u64 = 1 << 28;
+
+fn read_dimensions<R: Read>(reader: &mut R, what: &str) -> Result<(u32, u32), CheckpointError> {
+    let width = read_u32(reader)?;
+    let height = read_u32(reader)?;
+    match width.checked_mul(height) {
+        Some(pixels) if pixels > 0 && pixels as u64 <= MAX_PIXELS => Ok((width, height)),
+        _ => Err(CheckpointError::Invalid(format!("{} of {}x{} is not a render's size", what, width, height))),
+    }
+}
+
+fn read_kind<R: Read, T: Copy>(reader: &mut R, kinds: &[T], what: &str) -> Result<T, CheckpointError> {
+    let index = read_u32(reader)?;
+    kinds.get(index as usize).cloned().ok_or_else(|| CheckpointError::Invalid(format!("unknown {} {}", what, index)))
+}
+
+impl Checkpoint {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CheckpointError> {
+        Checkpoint::read(&mut io::BufReader::new(File::open(path)?))
+    }
+
+    // little endian throughout: the magic bytes, the scene name's length and bytes, the seed,
+    // the sampler, the filter and its radius, the resolution, then each pixel's sample count,
+    // red, green and blue sums and sum of squared luminance in rows, followed by the film's
+    // resolution and each of its pixels' red, green and blue sums and weight
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        write_u32(writer, self.scene.len() as u32)?;
+        writer.write_all(self.scene.as_bytes())?;
+        write_u32(writer, self.seed as u32)?;
+        write_u32(writer, (self.seed >> 32) as u32)?;
+        write_u32(writer, self.sampler as u32)?;
+        write_u32(writer, self.filter.kind as u32)?;
+        write_u32(writer, self.filter.radius.to_bits())?;
+        write_u32(writer, self.accumulation.sums.width())?;
+        write_u32(writer, self.accumulation.sums.height())?;
+        let accumulation = &self.accumulation;
+        for ((&samples, pixel), &sum_of_squares) in accumulation.samples.iter().zip(accumulation.sums.pixels()).zip(accumulation.sums_of_squares.iter()) {
+            write_u32(writer, samples)?;
+            for &channel in pixel.data.iter() {
+                write_u32(writer, channel.to_bits())?;
+            }
+            write_u32(writer, sum_of_squares.to_bits())?;
+        }
+        write_u32(writer, self.film.sums.width())?;
+        write_u32(writer, self.film.sums.height())?;
+        for (pixel, &weight) in self.film.sums.pixels().zip(self.film.weights.iter()) {
+            for &channel in pixel.data.iter() {
+                write_u32(writer, channel.to_bits())?;
+            }
+            write_u32(writer, weight.to_bits())?;
+        }
+        Ok(())
+    }
+
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self, CheckpointError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if OLDER_MAGICS.contains(&&magic) {
+            return Err(CheckpointError::Invalid("written by an older version of the raytracer".to_string()));
+        }
+        if &magic != MAGIC {
+            return Err(CheckpointError::Invalid("not a raytracer checkpoint".to_string()));
+        }
+
+        let scene_length = read_u32(reader)?;
+        let mut scene = Vec::new();
+        reader.take(scene_length as u64).read_to_end(&mut scene)?;
+        let scene = String::from_utf8(scene).map_err(|_| CheckpointError::Invalid("scene name is not utf-8".to_string()))?;
+        let seed = read_u32(reader)? as u64 | (read_u32(reader)? as u64) << 32;
+        let sampler = read_kind(reader, &SAMPLERS, "sampler")?;
+        let filter = Filter {
+            kind: read_kind(reader, &FILTERS, "filter")?,
+            radius: f32::from_bits(read_u32(reader)?),
+        };
+        let (width, height) = read_dimensions(reader, "resolution")?;
+
+        let mut accumulation = Accumulation::new(width, height);
+        for ((samples, pixel), sum_of_squares) in accumulation.samples.iter_mut().zip(accumulation.sums.pixels_mut()).zip(accumulation.sums_of_squares.iter_mut()) {
+            *samples = read_u32(reader)?;
+            for channel in pixel.data.iter_mut() {
+                *channel = f32::from_bits(read_u32(reader)?);
+            }
+            *sum_of_squares = f32::from_bits(read_u32(reader)?);
+        }
+
+        let (film_width, film_height) = read_dimensions(reader, "image")?;
+        let mut film = Film::new(film_width, film_height);
+        for (pixel, weight) in film.sums.pixels_mut().zip(film.weights.iter_mut()) {
+            for channel in pixel.data.iter_mut() {
+                *channel = f32::from_bits(read_u32(reader)?);
+            }
+            *weight = f32::from_bits(read_u32(reader)?);
+        }
+
+        Ok(Checkpoint { scene, seed, sampler, filter, accumulation, film })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> Checkpoint {
+        let mut tile = Accumulation::new(2, 2);
+        for &(x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)].iter() {
+            for _ in 0..4 {
+                tile.add_sample(x, y, Vector3::new(1.0, 0.5, 0.25));
+            }
+        }
+        let mut accumulation = Accumulation::new(3, 2);
+        accumulation.add_from(&tile, 1, 0);
+        let mut film = Film::new(6, 4);
+        film.add_sample(&Filter::default(), 2.5, 1.5, Vector3::new(1.0, 0.5, 0.25));
+        Checkpoint {
+            scene: "scenes/example.toml".to_string(),
+            seed: 1 << 40 | 7,
+            sampler: SamplerKind::Sobol,
+            filter: Filter { kind: FilterKind::Mitchell, radius: 2.0 },
+            accumulation,
+            film,
+        }
+    }
+
+    #[test]
+    fn accumulates_samples_per_pixel() {
+        let accumulation = checkpoint().accumulation;
+        assert_eq!(accumulation.mean_samples(), 16.0 / 6.0);
+        assert_eq!(accumulation.sums.get_pixel(2, 1).data, [4.0, 2.0, 1.0]);
+        assert_eq!(accumulation.samples[5], 4);
+        assert_eq!(accumulation.sums.get_pixel(0, 1).data, [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn relative_error_shrinks_with_more_samples() {
+        let mut accumulation = Accumulation::new(2, 1);
+        assert_eq!(accumulation.relative_error(0, 0), f32::INFINITY);
+        for i in 0..100 {
+            let value = if i % 2 == 0 { 1.0 } else { 3.0 };
+            accumulation.add_sample(0, 0, Vector3::new(value, value, value));
+            accumulation.add_sample(1, 0, Vector3::new(2.0, 2.0, 2.0));
+            if i == 9 {
+                assert!(accumulation.relative_error(0, 0) > 0.15);
+            }
+        }
+        // ones and threes have a standard deviation of one and a mean of two, so after 100 samples
+        // the standard error is a tenth, or a twentieth of the mean
+        assert!((accumulation.relative_error(0, 0) - 0.05).abs() < 0.001, "{}", accumulation.relative_error(0, 0));
+        assert!(accumulation.relative_error(1, 0) < 0.001);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let original = checkpoint();
+        let mut bytes = Vec::new();
+        original.write(&mut bytes).unwrap();
+        let read = Checkpoint::read(&mut &bytes[..]).unwrap();
+        assert_eq!(read.scene, original.scene);
+        assert_eq!(read.seed, original.seed);
+        assert_eq!((read.sampler, read.filter), (original.sampler, original.filter));
+        assert_eq!(read.accumulation.samples, original.accumulation.samples);
+        assert_eq!(read.accumulation.sums_of_squares, original.accumulation.sums_of_squares);
+        assert_eq!(read.accumulation.sums.into_raw(), original.accumulation.sums.into_raw());
+        assert_eq!(read.film.weights, original.film.weights);
+        assert_eq!(read.film.sums.into_raw(), original.film.sums.into_raw());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn loads_from_paths_which_are_not_utf8() {
+        use std::env;
+        use std::ffi::OsStr;
+        use std::fs;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = env::temp_dir().join(OsStr::from_bytes(b"raytracer-checkpoint-\xff.ck"));
+        checkpoint().write(&mut File::create(&path).unwrap()).unwrap();
+        let loaded = Checkpoint::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.unwrap().scene, "scenes/example.toml");
+    }
+
+    #[test]
+    fn rejects_other_files_and_truncation() {
+        match Checkpoint::read(&mut &b"\x89PNG\r\n\x1a\n"[..]) {
+            Err(CheckpointError::Invalid(_)) => {}
+            _ => panic!("expected an invalid checkpoint"),
+        }
+        for older in OLDER_MAGICS.iter() {
+            match Checkpoint::read(&mut &older[..]) {
+                Err(CheckpointError::Invalid(_)) => {}
+                _ => panic!("expected an older checkpoint to be refused"),
+            }
+        }
+        // a resolution which would overflow, or be far too large to allocate
+        let mut bytes = Vec::new();
+        checkpoint().write(&mut bytes).unwrap();
+        let resolution = 8 + 4 + "scenes/example.toml".len() + 8 + 12;
+        for &(width, height) in [(0xffff_ffffu32, 0xffff_ffffu32), (1 << 20, 1 << 20), (0, 4)].iter() {
+            write_u32(&mut &mut bytes[resolution..], width).unwrap();
+            write_u32(&mut &mut bytes[resolution + 4..], height).unwrap();
+            match Checkpoint::read(&mut &bytes[..]) {
+                Err(CheckpointError::Invalid(_)) => {}
+                _ => panic!("expected a {}x{} checkpoint to be refused", width, height),
+            }
+        }
+        let mut bytes = Vec::new();
+        checkpoint().write(&mut bytes).unwrap();
+        bytes.pop();
+        match Checkpoint::read(&mut &bytes[..]) {
+            Err(CheckpointError::Io(_)) => {}
+            _ => panic!("expected a truncated checkpoint to fail"),
+        }
+    }
+}
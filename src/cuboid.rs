@@ -0,0 +1,171 @@
+use hitable::{
+    Hitable,
+    HitRecord,
+};
+use ray::Ray3;
+use aabb::AABB;
+use material::Material;
+use instance::Instance;
+use transform::Transform;
+use stats::{
+    self,
+    Primitive,
+};
+
+use cgmath::{
+    EuclideanSpace,
+    Point3,
+    Quaternion,
+    Vector3,
+    Zero,
+};
+
+use std::sync::Arc;
+
+/// A face of a cuboid, named as seen from the front, along -z.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Face {
+    Left,
+    Right,
+    Bottom,
+    Top,
+    Back,
+    Front,
+}
+
+// in order of axis, with the face at the low end of each axis first
+const FACES: [Face; 6] = [Face::Left, Face::Right, Face::Bottom, Face::Top, Face::Back, Face::Front];
+
+/// An axis-aligned box, which can have a different material on each face.
+pub struct Cuboid {
+    bounds: AABB,
+    materials: [Material; 6],
+}
+
+impl Cuboid {
+    /// The box between two opposite corners.
+    pub fn new(corner0: Point3<f32>, corner1: Point3<f32>, material: Material) -> Self {
+        Cuboid {
+            bounds: AABB::new(
+                Point3::new(corner0.x.min(corner1.x), corner0.y.min(corner1.y), corner0.z.min(corner1.z)),
+                Point3::new(corner0.x.max(corner1.x), corner0.y.max(corner1.y), corner0.z.max(corner1.z)),
+            ),
+            materials: [material.clone(), material.clone(), material.clone(), material.clone(), material.clone(), material],
+        }
+    }
+
+    pub fn with_face_material(mut self, face: Face, material: Material) -> Self {
+        self.materials[face as usize] = material;
+        self
+    }
+
+    /// The box turned by `rotation` about its centre.
+    pub fn oriented(self, rotation: Quaternion<f32>) -> Instance {
+        let centre = self.bounds.centroid().to_vec();
+        let transform = Transform::translation(-centre)
+            .then(&Transform::rotation(rotation))
+            .then(&Transform::translation(centre));
+        Instance::new(Arc::new(self), transform)
+    }
+}
+
+impl Hitable for Cuboid {
+    fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        stats::count_hit_test(Primitive::Cuboid);
+        let (entry, exit) = self.bounds.entry_and_exit(r)?;
+        // rays starting inside the box, such as those refracted into it, hit it where they leave
+        let ((t, axis), leaving) = if entry.0 > t_min { (entry, false) } else { (exit, true) };
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let high_side = (r.direction[axis] > 0f32) == leaving;
+        let face = FACES[2 * axis + high_side as usize];
+        let mut normal = Vector3::zero();
+        normal[axis] = if high_side { 1f32 } else { -1f32 };
+
+        // seen from outside each face, u runs to the right and v upwards, with the top seen from
+        // the front and the bottom from the back
+        let p = r.point_at_parameter(t);
+        let extent = self.bounds.max - self.bounds.min;
+        let fraction = |a: usize| if extent[a] > 0f32 { (p[a] - self.bounds.min[a]) / extent[a] } else { 0f32 };
+        let (x, y, z) = (fraction(0), fraction(1), fraction(2));
+        let (u, v) = match face {
+            Face::Left => (z, y),
+            Face::Right => (1f32 - z, y),
+            Face::Bottom => (x, z),
+            Face::Top => (x, 1f32 - z),
+            Face::Back => (1f32 - x, y),
+            Face::Front => (x, y),
+        };
+
+        Some(HitRecord::new(t, p, normal, Some(self.materials[face as usize].clone())).with_uv(u, v))
+    }
+
+    fn bounding_box(&self, _: f32, _: f32) -> Option<AABB> {
+        Some(self.bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cgmath::{
+        Deg,
+        InnerSpace,
+        Rotation3,
+    };
+
+    fn unit_cuboid() -> Cuboid {
+        Cuboid::new(Point3::new(1.0, 1.0, 1.0), Point3::new(-1.0, -1.0, -1.0), Material::Lambertian { albedo: Vector3::new(0.5, 0.5, 0.5) })
+    }
+
+    #[test]
+    fn hits_the_face_facing_the_ray() {
+        let r = Ray3::new(Point3::new(0.5, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = unit_cuboid().hit(&r, 0.001, 100.0).unwrap();
+        assert_eq!(rec.t, 4.0);
+        assert_eq!(rec.normal, Vector3::unit_z());
+        assert_eq!((rec.u, rec.v), (0.75, 0.5));
+        assert!(unit_cuboid().hit(&r, 0.001, 3.0).is_none());
+
+        let r = Ray3::new(Point3::new(0.0, -5.0, 0.0), Vector3::new(0.0, 2.0, 0.0), 0.0);
+        assert_eq!(unit_cuboid().hit(&r, 0.001, 100.0).unwrap().normal, -Vector3::unit_y());
+    }
+
+    #[test]
+    fn rays_from_inside_hit_the_far_face_with_an_outward_normal() {
+        let r = Ray3::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0), 0.0);
+        let rec = unit_cuboid().hit(&r, 0.001, 100.0).unwrap();
+        assert_eq!(rec.t, 1.0);
+        assert_eq!(rec.normal, Vector3::unit_x());
+    }
+
+    #[test]
+    fn faces_can_have_their_own_materials() {
+        let cuboid = unit_cuboid().with_face_material(Face::Top, Material::Dielectric { ref_idx: 1.5 });
+        let down = Ray3::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 0.0);
+        match cuboid.hit(&down, 0.001, 100.0).unwrap().material {
+            Some(Material::Dielectric { ref_idx }) => assert_eq!(ref_idx, 1.5),
+            _ => panic!("expected the top face's material"),
+        }
+        let up = Ray3::new(Point3::new(0.0, -5.0, 0.0), Vector3::new(0.0, 1.0, 0.0), 0.0);
+        match cuboid.hit(&up, 0.001, 100.0).unwrap().material {
+            Some(Material::Lambertian { .. }) => {}
+            _ => panic!("expected the cuboid's material"),
+        }
+    }
+
+    #[test]
+    fn oriented_cuboids_turn_about_their_centre() {
+        let cuboid = Cuboid::new(Point3::new(2.0, 0.0, -1.0), Point3::new(4.0, 2.0, 1.0), Material::Dielectric { ref_idx: 1.5 })
+            .oriented(Quaternion::from_angle_y(Deg(45.0)));
+        let r = Ray3::new(Point3::new(3.2, 1.0, 5.0), Vector3::new(0.0, 0.0, -1.0), 0.0);
+        let rec = cuboid.hit(&r, 0.001, 100.0).unwrap();
+        assert!((rec.t - (5.2 - 2f32.sqrt())).abs() < 0.0001);
+        assert!((rec.normal - Vector3::new(1.0, 0.0, 1.0).normalize()).magnitude() < 0.0001);
+        let bounding_box = cuboid.bounding_box(0.0, 0.0).unwrap();
+        assert!((bounding_box.max.x - (3.0 + 2f32.sqrt())).abs() < 0.0001);
+    }
+}
@@ -0,0 +1,274 @@
+use light::{
+    Light,
+    LightSample,
+};
+use sampler::Sampler;
+use output::luminance;
+
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace,
+};
+use image;
+use image::hdr::HDRDecoder;
+
+use std::f32;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+
+/// The light arriving from rays which escape the scene.
+pub enum Environment {
+    Constant {
+        colour: Vector3<f32>,
+    },
+    Gradient {
+        bottom: Vector3<f32>,
+        top: Vector3<f32>,
+    },
+    Image(EnvironmentMap),
+}
+
+impl Environment {
+    /// The original sky, fading from white below to blue above.
+    pub fn sky() -> Self {
+        Environment::Gradient {
+            bottom: Vector3::new(1.0, 1.0, 1.0),
+            top: Vector3::new(0.5, 0.7, 1.0),
+        }
+    }
+
+    pub fn value(&self, direction: Vector3<f32>) -> Vector3<f32> {
+        match *self {
+            Environment::Constant { colour } => colour,
+            Environment::Gradient { bottom, top } => {
+                let unit_direction = direction.normalize();
+                let t = 0.5 * (unit_direction.y + 1.0);
+                (1.0 - t) * bottom + t * top
+            }
+            Environment::Image(ref map) => map.value(direction),
+        }
+    }
+}
+
+/// An equirectangular image of the surroundings, with +y up and the centre of the image facing -x
+/// before `rotation` (in radians) is applied about the vertical axis.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vector3<f32>>,
+    rotation: f32,
+    intensity: f32,
+    // cumulative pixel weights for choosing pixels in proportion to the light they contribute
+    cdf: Vec<f32>,
+}
+
+impl EnvironmentMap {
+    pub fn new(width: usize, height: usize, pixels: Vec<Vector3<f32>>, rotation_degrees: f32, intensity: f32) -> Self {
+        let mut map = EnvironmentMap {
+            width,
+            height,
+            pixels,
+            rotation: rotation_degrees.to_radians(),
+            intensity,
+            cdf: Vec::new(),
+        };
+
+        // pixels near the poles cover less of the sphere, so are chosen less often
+        let mut total = 0f32;
+        map.cdf = map.pixels.iter().enumerate().map(|(i, &pixel)| {
+            total += luminance(pixel).max(0f32) * map.solid_angle(i);
+            total
+        }).collect();
+
+        map
+    }
+
+    fn row_cosines(&self, index: usize) -> (f32, f32) {
+        let row = (index / self.width) as f32;
+        let cos_top = (row / self.height as f32 * f32::consts::PI).cos();
+        let cos_bottom = ((row + 1f32) / self.height as f32 * f32::consts::PI).cos();
+        (cos_top, cos_bottom)
+    }
+
+    fn solid_angle(&self, index: usize) -> f32 {
+        let (cos_top, cos_bottom) = self.row_cosines(index);
+        2f32 * f32::consts::PI / self.width as f32 * (cos_top - cos_bottom)
+    }
+
+    /// Loads a Radiance `.hdr` image.
+    pub fn load(path: &str, rotation_degrees: f32, intensity: f32) -> image::ImageResult<Self> {
+        let decoder = HDRDecoder::new(BufReader::new(File::open(path)?))?;
+        let metadata = decoder.metadata();
+        let pixels = decoder.read_image_hdr()?.iter()
+            .map(|pixel| Vector3::new(pixel.data[0], pixel.data[1], pixel.data[2]))
+            .collect();
+        Ok(Self::new(metadata.width as usize, metadata.height as usize, pixels, rotation_degrees, intensity))
+    }
+
+    fn pixel_index(&self, direction: Vector3<f32>) -> usize {
+        let d = direction.normalize();
+        let phi = d.z.atan2(d.x) + self.rotation;
+        let u = phi / (2f32 * f32::consts::PI);
+        let u = u - u.floor();
+        let v = d.y.max(-1f32).min(1f32).acos() / f32::consts::PI;
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+        y * self.width + x
+    }
+
+    pub fn value(&self, direction: Vector3<f32>) -> Vector3<f32> {
+        self.intensity * self.pixels[self.pixel_index(direction)]
+    }
+
+    fn total_weight(&self) -> f32 {
+        *self.cdf.last().unwrap_or(&0f32)
+    }
+
+    // directions are uniformly distributed over the solid angle of the chosen pixel
+    fn pixel_pdf(&self, index: usize) -> f32 {
+        let weight = self.cdf[index] - if index == 0 { 0f32 } else { self.cdf[index - 1] };
+        weight / self.total_weight() / self.solid_angle(index)
+    }
+
+    pub fn sample(&self, sampler: &mut Sampler) -> Option<(Vector3<f32>, f32)> {
+        let total = self.total_weight();
+        if total <= 0f32 {
+            return None;
+        }
+        let target = sampler.next_1d() * total;
+        let index = match self.cdf.binary_search_by(|weight| weight.partial_cmp(&target).unwrap()) {
+            Ok(index) => index,
+            Err(index) => index,
+        }.min(self.cdf.len() - 1);
+
+        let (r1, r2) = sampler.next_2d();
+        let u = ((index % self.width) as f32 + r1) / self.width as f32;
+        let phi = u * 2f32 * f32::consts::PI - self.rotation;
+        let (cos_top, cos_bottom) = self.row_cosines(index);
+        let cos_theta = cos_top + r2 * (cos_bottom - cos_top);
+        let sin_theta = (1f32 - cos_theta * cos_theta).max(0f32).sqrt();
+        let direction = Vector3::new(sin_theta * phi.cos(), cos_theta, sin_theta * phi.sin());
+
+        Some((direction, self.pixel_pdf(index)))
+    }
+
+    pub fn pdf(&self, direction: Vector3<f32>) -> f32 {
+        if self.total_weight() <= 0f32 {
+            0f32
+        } else {
+            self.pixel_pdf(self.pixel_index(direction))
+        }
+    }
+}
+
+/// Samples the bright parts of an environment image directly, as if it were an infinitely distant light.
+pub struct EnvironmentLight {
+    environment: Arc<Environment>,
+}
+
+impl EnvironmentLight {
+    pub fn new(environment: Arc<Environment>) -> Self {
+        EnvironmentLight { environment }
+    }
+}
+
+impl Light for EnvironmentLight {
+    fn sample(&self, _: Point3<f32>, sampler: &mut Sampler) -> Option<LightSample> {
+        match *self.environment {
+            Environment::Image(ref map) => map.sample(sampler).map(|(direction, pdf)| LightSample {
+                direction,
+                distance: f32::INFINITY,
+                pdf,
+            }),
+            _ => None,
+        }
+    }
+
+    fn pdf(&self, _: Point3<f32>, direction: Vector3<f32>) -> f32 {
+        match *self.environment {
+            Environment::Image(ref map) => map.pdf(direction),
+            _ => 0f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sampler::RandomSampler;
+    use image::hdr::HDREncoder;
+    use image::Rgb;
+
+    use std::env;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() <= 0.001 * b.abs(), "{} != {}", a, b);
+    }
+
+    fn bright_spot_map(rotation: f32) -> EnvironmentMap {
+        let mut pixels = vec![Vector3::new(0.1, 0.1, 0.1); 8 * 4];
+        pixels[1 * 8 + 2] = Vector3::new(50.0, 50.0, 50.0);
+        EnvironmentMap::new(8, 4, pixels, rotation, 2.0)
+    }
+
+    #[test]
+    fn sky_matches_original_gradient() {
+        let sky = Environment::sky();
+        assert_eq!(sky.value(Vector3::unit_y()), Vector3::new(0.5, 0.7, 1.0));
+        assert_eq!(sky.value(-Vector3::unit_y()), Vector3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn samples_favour_bright_pixels_and_match_pdf() {
+        let map = bright_spot_map(30.0);
+        let mut sampler = RandomSampler::new(0);
+        let mut bright = 0;
+        for i in 0..200 {
+            sampler.start_sample(0, i);
+            let (direction, pdf) = map.sample(&mut sampler).unwrap();
+            assert_close(map.pdf(direction), pdf);
+            if map.value(direction).x > 1.0 {
+                bright += 1;
+            }
+        }
+        assert!(bright > 150);
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        let map = bright_spot_map(0.0);
+        let steps = 400;
+        let mut integral = 0f32;
+        for j in 0..steps {
+            let theta = (j as f32 + 0.5) / steps as f32 * f32::consts::PI;
+            for i in 0..steps {
+                let phi = (i as f32 + 0.5) / steps as f32 * 2f32 * f32::consts::PI;
+                let direction = Vector3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+                integral += map.pdf(direction) * theta.sin() * (f32::consts::PI / steps as f32) * (2f32 * f32::consts::PI / steps as f32);
+            }
+        }
+        assert!((integral - 1.0).abs() < 0.02, "{}", integral);
+    }
+
+    #[test]
+    fn rotation_turns_the_image_about_the_vertical_axis() {
+        let unrotated = bright_spot_map(0.0);
+        let rotated = bright_spot_map(90.0);
+        let direction = Vector3::new(1.0, 0.2, 0.3);
+        let turned = Vector3::new(-direction.z, direction.y, direction.x);
+        assert_eq!(rotated.value(direction), unrotated.value(turned));
+    }
+
+    #[test]
+    fn loads_radiance_hdr() {
+        let path = env::temp_dir().join("raytracer-environment-test.hdr");
+        let pixels = vec![Rgb { data: [1.0, 2.0, 4.0] }, Rgb { data: [0.5, 0.5, 0.5] }];
+        HDREncoder::new(File::create(&path).unwrap()).encode(&pixels, 2, 1).unwrap();
+
+        let map = EnvironmentMap::load(path.to_str().unwrap(), 0.0, 1.0).unwrap();
+        assert_eq!((map.width, map.height), (2, 1));
+        assert_eq!(map.pixels[0], Vector3::new(1.0, 2.0, 4.0));
+    }
+}
@@ -0,0 +1,85 @@
+use scenefile::SceneFileError;
+use checkpoint::CheckpointError;
+
+use std::{
+    error,
+    fmt,
+    io,
+    path,
+    result,
+};
+
+/// Everything which can stop a render, each naming the argument, scene or file at fault.
+#[derive(Debug)]
+pub enum Error {
+    Argument {
+        name: String,
+        value: String,
+        message: String,
+    },
+    UnknownScene(String),
+    Scene {
+        name: String,
+        error: SceneFileError,
+    },
+    Checkpoint {
+        path: path::PathBuf,
+        error: CheckpointError,
+    },
+    CreateDirectory {
+        path: path::PathBuf,
+        error: io::Error,
+    },
+    Write {
+        path: path::PathBuf,
+        error: io::Error,
+    },
+    Stdout(io::Error),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Argument { ref name, ref value, ref message } => write!(f, "invalid value '{}' for --{}: {}", value, name, message),
+            Error::UnknownScene(ref name) => write!(f, "unknown scene '{}'", name),
+            Error::Scene { ref name, ref error } => write!(f, "{}: {}", name, error),
+            Error::Checkpoint { ref path, ref error } => write!(f, "{}: {}", path.display(), error),
+            Error::CreateDirectory { ref path, ref error } => write!(f, "could not create directory {}: {}", path.display(), error),
+            Error::Write { ref path, ref error } => write!(f, "could not write {}: {}", path.display(), error),
+            Error::Stdout(ref error) => write!(f, "could not write to standard output: {}", error),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Argument { .. } => "invalid argument",
+            Error::UnknownScene(_) => "unknown scene",
+            Error::Scene { .. } => "invalid scene",
+            Error::Checkpoint { .. } => "invalid checkpoint",
+            Error::CreateDirectory { .. } => "could not create directory",
+            Error::Write { .. } => "could not write file",
+            Error::Stdout(_) => "could not write to standard output",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_name_what_went_wrong() {
+        let argument = Error::Argument { name: "nx".to_string(), value: "abc".to_string(), message: "invalid digit found in string".to_string() };
+        assert_eq!(argument.to_string(), "invalid value 'abc' for --nx: invalid digit found in string");
+
+        let write = Error::Write { path: path::PathBuf::from("/missing/out.png"), error: io::Error::new(io::ErrorKind::NotFound, "No such file or directory") };
+        assert_eq!(write.to_string(), "could not write /missing/out.png: No such file or directory");
+
+        let checkpoint = Error::Checkpoint { path: path::PathBuf::from("render.ck"), error: CheckpointError::Invalid("truncated".to_string()) };
+        assert_eq!(checkpoint.to_string(), "render.ck: invalid checkpoint: truncated");
+    }
+}
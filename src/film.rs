@@ -0,0 +1,127 @@
+use filter::Filter;
+use output::RadianceImage;
+
+use cgmath::Vector3;
+use image::Rgb;
+
+/// The filter weighted sums of the samples around each pixel of the output image, along with
+/// the sum of the weights they were given.
+#[derive(Clone)]
+pub struct Film {
+    pub sums: RadianceImage,
+    pub weights: Vec<f32>,
+}
+
+/// The range of pixels along one axis which a filter of `radius` pixels reaches from samples
+/// between `start` and `end`, measured in pixels. The range may run off either edge of the image.
+pub fn reach(radius: f32, start: f32, end: f32) -> (i32, i32) {
+    ((start - 0.5 - radius).floor() as i32, (end - 0.5 + radius).ceil() as i32)
+}
+
+impl Film {
+    pub fn new(width: u32, height: u32) -> Self {
+        Film {
+            sums: RadianceImage::new(width, height),
+            weights: vec![0f32; (width * height) as usize],
+        }
+    }
+
+    /// Splats a sample at (x, y), in pixels from the top left of the film, into every pixel
+    /// whose centre is within the filter's radius.
+    pub fn add_sample(&mut self, filter: &Filter, x: f32, y: f32, colour: Vector3<f32>) {
+        self.add_stretched_sample(filter, (1f32, 1f32), x, y, colour);
+    }
+
+    /// Splats a sample like `add_sample`, with the filter stretched along each axis by `stretch`.
+    /// Samples taken more sparsely than the film's pixels are stretched over as many pixels as
+    /// lie between them, so that every pixel is reached.
+    pub fn add_stretched_sample(&mut self, filter: &Filter, stretch: (f32, f32), x: f32, y: f32, colour: Vector3<f32>) {
+        let (width, height) = self.sums.dimensions();
+        let (left, right) = reach(filter.radius * stretch.0, x, x);
+        let (top, bottom) = reach(filter.radius * stretch.1, y, y);
+        for pixel_y in top.max(0)..(bottom + 1).min(height as i32) {
+            for pixel_x in left.max(0)..(right + 1).min(width as i32) {
+                let weight = filter.evaluate((x - (pixel_x as f32 + 0.5)) / stretch.0, (y - (pixel_y as f32 + 0.5)) / stretch.1);
+                if weight == 0f32 {
+                    continue;
+                }
+                let total = self.sums.get_pixel_mut(pixel_x as u32, pixel_y as u32);
+                total.data[0] += weight * colour.x;
+                total.data[1] += weight * colour.y;
+                total.data[2] += weight * colour.z;
+                self.weights[(pixel_y as u32 * width + pixel_x as u32) as usize] += weight;
+            }
+        }
+    }
+
+    /// Adds a smaller film, such as one for a tile, to this one with its top left at (x, y).
+    /// Tile films reach past their tile by the filter's radius, so the parts of them which fall
+    /// outside this film are dropped.
+    pub fn add_from(&mut self, other: &Film, x: i32, y: i32) {
+        let (width, height) = self.sums.dimensions();
+        for (other_x, other_y, pixel) in other.sums.enumerate_pixels() {
+            let (film_x, film_y) = (x + other_x as i32, y + other_y as i32);
+            if film_x < 0 || film_y < 0 || film_x >= width as i32 || film_y >= height as i32 {
+                continue;
+            }
+            let total = self.sums.get_pixel_mut(film_x as u32, film_y as u32);
+            for (total_channel, channel) in total.data.iter_mut().zip(pixel.data.iter()) {
+                *total_channel += *channel;
+            }
+            self.weights[(film_y as u32 * width + film_x as u32) as usize] += other.weights[(other_y * other.sums.width() + other_x) as usize];
+        }
+    }
+
+    // pixels which no sample reached, or whose weights cancelled out, are black
+    pub fn image(&self) -> RadianceImage {
+        let width = self.sums.width();
+        RadianceImage::from_fn(width, self.sums.height(), |x, y| {
+            let weight = self.weights[(y * width + x) as usize];
+            let sum = self.sums.get_pixel(x, y).data;
+            if weight == 0f32 {
+                Rgb { data: [0f32, 0f32, 0f32] }
+            } else {
+                Rgb { data: [sum[0] / weight, sum[1] / weight, sum[2] / weight] }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use filter::FilterKind;
+
+    #[test]
+    fn box_filtered_samples_average_within_their_pixel() {
+        let filter = Filter::default();
+        let mut film = Film::new(2, 1);
+        film.add_sample(&filter, 0.0, 0.5, Vector3::new(1.0, 2.0, 3.0));
+        film.add_sample(&filter, 0.99, 0.5, Vector3::new(3.0, 2.0, 1.0));
+        assert_eq!(film.weights, vec![2.0, 0.0]);
+        assert_eq!(film.image().get_pixel(0, 0).data, [2.0, 2.0, 2.0]);
+        assert_eq!(film.image().get_pixel(1, 0).data, [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn wider_filters_reach_neighbouring_pixels() {
+        let filter = Filter { kind: FilterKind::Tent, radius: 1.0 };
+        let mut film = Film::new(3, 1);
+        film.add_sample(&filter, 1.25, 0.5, Vector3::new(1.0, 1.0, 1.0));
+        assert_eq!(film.weights, vec![0.25, 0.75, 0.0]);
+        assert_eq!(film.image().get_pixel(0, 0).data, [1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn tiles_are_clipped_to_the_film() {
+        let filter = Filter { kind: FilterKind::Tent, radius: 1.0 };
+        let (left, right) = reach(filter.radius, 0.0, 2.0);
+        assert_eq!((left, right), (-2, 3));
+        let mut tile = Film::new((right - left + 1) as u32, 1);
+        tile.add_sample(&filter, 0.25 - left as f32, 0.5, Vector3::new(1.0, 1.0, 1.0));
+
+        let mut film = Film::new(2, 1);
+        film.add_from(&tile, left, 0);
+        assert_eq!(film.weights, vec![0.75, 0.0]);
+    }
+}
@@ -0,0 +1,128 @@
+use std::f32;
+
+/// The shape with which each sample is spread over the pixels around it.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FilterKind {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+impl FilterKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "box" => Some(FilterKind::Box),
+            "tent" => Some(FilterKind::Tent),
+            "gaussian" => Some(FilterKind::Gaussian),
+            "mitchell" => Some(FilterKind::Mitchell),
+            "lanczos" => Some(FilterKind::Lanczos),
+            _ => None,
+        }
+    }
+
+    /// The radius in pixels used when none is given, chosen so that each filter keeps its
+    /// usual shape.
+    pub fn default_radius(&self) -> f32 {
+        match *self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.0,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.0,
+            FilterKind::Lanczos => 3.0,
+        }
+    }
+
+    // the weight at an offset of x pixels along one axis
+    fn evaluate(&self, x: f32, radius: f32) -> f32 {
+        match *self {
+            // a sample exactly between two pixels only counts towards one of them
+            FilterKind::Box => if -radius <= x && x < radius { 1f32 } else { 0f32 },
+            FilterKind::Tent => (radius - x.abs()).max(0f32),
+            FilterKind::Gaussian => {
+                let alpha = 2f32;
+                ((-alpha * x * x).exp() - (-alpha * radius * radius).exp()).max(0f32)
+            }
+            FilterKind::Mitchell => mitchell(2f32 * x.abs() / radius),
+            FilterKind::Lanczos => if x.abs() < radius { sinc(x.abs()) * sinc(x.abs() / radius) } else { 0f32 },
+        }
+    }
+}
+
+// the Mitchell-Netravali cubic with B = C = 1/3 over [-2, 2]
+fn mitchell(x: f32) -> f32 {
+    let (b, c) = (1f32 / 3f32, 1f32 / 3f32);
+    if x > 2f32 {
+        0f32
+    } else if x > 1f32 {
+        ((-b - 6f32 * c) * x * x * x + (6f32 * b + 30f32 * c) * x * x + (-12f32 * b - 48f32 * c) * x + (8f32 * b + 24f32 * c)) / 6f32
+    } else {
+        ((12f32 - 9f32 * b - 6f32 * c) * x * x * x + (-18f32 + 12f32 * b + 6f32 * c) * x * x + (6f32 - 2f32 * b)) / 6f32
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x < 0.00001 {
+        1f32
+    } else {
+        let pi_x = f32::consts::PI * x;
+        pi_x.sin() / pi_x
+    }
+}
+
+/// A separable reconstruction filter reaching `radius` pixels from each sample along each axis.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Filter {
+    pub kind: FilterKind,
+    pub radius: f32,
+}
+
+impl Default for Filter {
+    // one sample per pixel, as though the samples were simply averaged
+    fn default() -> Self {
+        Filter {
+            kind: FilterKind::Box,
+            radius: FilterKind::Box.default_radius(),
+        }
+    }
+}
+
+impl Filter {
+    /// The weight of a sample (x, y) pixels from a pixel's centre. The Mitchell and Lanczos
+    /// filters have negative lobes, which sharpen the image.
+    pub fn evaluate(&self, x: f32, y: f32) -> f32 {
+        self.kind.evaluate(x, self.radius) * self.kind.evaluate(y, self.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn box_covers_a_single_pixel() {
+        let filter = Filter::default();
+        assert_eq!(filter.evaluate(0.0, 0.0), 1.0);
+        assert_eq!(filter.evaluate(-0.5, 0.2), 1.0);
+        assert_eq!(filter.evaluate(0.5, 0.2), 0.0);
+    }
+
+    #[test]
+    fn filters_peak_at_the_centre_and_vanish_at_their_radius() {
+        for &kind in [FilterKind::Tent, FilterKind::Gaussian, FilterKind::Mitchell, FilterKind::Lanczos].iter() {
+            let filter = Filter { kind, radius: kind.default_radius() };
+            let centre = filter.evaluate(0.0, 0.0);
+            assert!(centre > 0.0, "{:?}", kind);
+            assert!(filter.evaluate(0.3, 0.0) < centre, "{:?}", kind);
+            assert!(filter.evaluate(filter.radius, 0.0).abs() < 0.0001, "{:?}", kind);
+            assert_eq!(filter.evaluate(filter.radius + 0.1, 0.0), 0.0, "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn mitchell_and_lanczos_have_negative_lobes() {
+        assert!(Filter { kind: FilterKind::Mitchell, radius: 2.0 }.evaluate(1.5, 0.0) < 0.0);
+        assert!(Filter { kind: FilterKind::Lanczos, radius: 3.0 }.evaluate(1.5, 0.0) < 0.0);
+    }
+}
@@ -1,13 +1,14 @@
 use ray::Ray3;
 use aabb::AABB;
 use material::Material;
+use bvh::BVHNode;
 
 use cgmath::{
     Vector3,
     Point3,
 };
 
-use std::time::Instant;
+use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct HitRecord {
@@ -15,21 +16,58 @@ pub struct HitRecord {
     pub p: Point3<f32>,
     pub normal: Vector3<f32>,
     pub material: Option<Material>,
+    pub u: f32,
+    pub v: f32,
+    // the scene object which was hit, starting from one so that zero can mean nothing was hit
+    pub id: u32,
 }
 
 impl HitRecord {
     pub fn new(t: f32, p: Point3<f32>, normal: Vector3<f32>, material: Option<Material>) -> Self {
-        HitRecord { t, p, normal, material }
+        HitRecord { t, p, normal, material, u: 0f32, v: 0f32, id: 0 }
+    }
+
+    pub fn with_uv(mut self, u: f32, v: f32) -> Self {
+        self.u = u;
+        self.v = v;
+        self
+    }
+
+    pub fn with_id(mut self, id: u32) -> Self {
+        self.id = id;
+        self
     }
 }
 
 pub trait Hitable {
     fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord>;
-    fn bounding_box(&self, t0: Instant, t1: Instant) -> Option<AABB>;
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB>;
+}
+
+/// Labels every hit on a hitable with an id, so that render passes can tell scene objects apart.
+pub struct Identified {
+    id: u32,
+    hitable: Box<Hitable + Sync + Send>,
+}
+
+impl Identified {
+    pub fn new(id: u32, hitable: Box<Hitable + Sync + Send>) -> Self {
+        Identified { id, hitable }
+    }
+}
+
+impl Hitable for Identified {
+    fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        self.hitable.hit(r, t_min, t_max).map(|rec| rec.with_id(self.id))
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
+        self.hitable.bounding_box(t0, t1)
+    }
 }
 
 pub struct HitableList {
-    list: Vec<Box<Hitable + Sync + Send>>,
+    list: Vec<Arc<Hitable + Sync + Send>>,
 }
 
 impl HitableList {
@@ -38,12 +76,32 @@ impl HitableList {
     }
 
     pub fn with_hitable<H>(mut self, hitable: H) -> Self where H: Hitable + Sync + Send + 'static {
-        self.list.push(Box::new(hitable));
+        self.list.push(Arc::new(hitable));
         self
     }
 
     pub fn add_hitable<H>(&mut self, hitable: H) where H: Hitable + Sync + Send + 'static {
-        self.list.push(Box::new(hitable))
+        self.list.push(Arc::new(hitable))
+    }
+
+    /// Adds a hitable which other lists may hold too.
+    pub fn with_shared(mut self, hitable: Arc<Hitable + Sync + Send>) -> Self {
+        self.list.push(hitable);
+        self
+    }
+
+    /// Gathers every bounded hitable under a single BVH. Unbounded hitables such as planes
+    /// have no bounding box and are kept alongside it to be tested linearly.
+    pub fn into_bvh(self, t0: f32, t1: f32) -> Self {
+        let (bounded, unbounded): (Vec<_>, Vec<_>) = self.list.into_iter().partition(|hitable| {
+            hitable.bounding_box(t0, t1).is_some()
+        });
+
+        let mut list = unbounded;
+        if !bounded.is_empty() {
+            list.push(Arc::new(BVHNode::new(bounded, t0, t1)));
+        }
+        HitableList { list }
     }
 }
 
@@ -88,7 +146,7 @@ impl Hitable for HitableList {
     //        })
     //    }
 
-    fn bounding_box(&self, t0: Instant, t1: Instant) -> Option<AABB> {
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
         if self.list.is_empty() {
             None
         } else {
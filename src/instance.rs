@@ -0,0 +1,156 @@
+use hitable::{
+    Hitable,
+    HitRecord,
+};
+use ray::Ray3;
+use aabb::AABB;
+use material::Material;
+use transform::{
+    Transform,
+    AnimatedTransform,
+};
+
+use cgmath::InnerSpace;
+
+use std::sync::Arc;
+
+/// Places shared geometry in the world with a transform, so that many copies of a mesh cost
+/// no more memory than one. The instance's material, if it has one, replaces the geometry's own.
+pub struct Instance {
+    hitable: Arc<Hitable + Send + Sync>,
+    transform: Transform,
+    material: Option<Material>,
+}
+
+impl Instance {
+    pub fn new(hitable: Arc<Hitable + Send + Sync>, transform: Transform) -> Self {
+        Instance {
+            hitable,
+            transform,
+            material: None,
+        }
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = Some(material);
+        self
+    }
+}
+
+/// An instance which moves through keyframes while the shutter is open.
+pub struct MovingInstance {
+    hitable: Arc<Hitable + Send + Sync>,
+    motion: AnimatedTransform,
+    material: Option<Material>,
+}
+
+impl MovingInstance {
+    pub fn new(hitable: Arc<Hitable + Send + Sync>, motion: AnimatedTransform) -> Self {
+        MovingInstance {
+            hitable,
+            motion,
+            material: None,
+        }
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = Some(material);
+        self
+    }
+}
+
+fn hit_transformed(hitable: &Hitable, transform: &Transform, material: &Option<Material>, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+    // the object space direction is not normalised, so t is the same in both spaces
+    hitable.hit(&transform.ray_to_object(r), t_min, t_max).map(|rec| {
+        let mut world_rec = HitRecord {
+            p: r.point_at_parameter(rec.t),
+            normal: transform.normal(rec.normal).normalize(),
+            ..rec
+        };
+        if material.is_some() {
+            world_rec.material = material.clone();
+        }
+        world_rec
+    })
+}
+
+impl Hitable for Instance {
+    fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        hit_transformed(&*self.hitable, &self.transform, &self.material, r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
+        self.hitable.bounding_box(t0, t1).map(|bounding_box| self.transform.bounding_box(&bounding_box))
+    }
+}
+
+impl Hitable for MovingInstance {
+    fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        hit_transformed(&*self.hitable, &self.motion.at(r.time), &self.material, r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
+        self.hitable.bounding_box(t0, t1).map(|bounding_box| self.motion.bounding_box(&bounding_box, t0, t1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sphere::Sphere;
+    use transform::Keyframe;
+    use animation::Curve;
+
+    use cgmath::{
+        One,
+        Point3,
+        Quaternion,
+        Vector3,
+    };
+
+    fn unit_sphere() -> Arc<Hitable + Send + Sync> {
+        Arc::new(Sphere::new(Point3::new(0.0, 0.0, 0.0), 1.0, Material::Lambertian { albedo: Vector3::new(0.5, 0.5, 0.5) }))
+    }
+
+    #[test]
+    fn hits_squashed_geometry_where_it_was_moved() {
+        let instance = Instance::new(unit_sphere(), Transform::scale(1.0, 0.5, 1.0).then(&Transform::translation(Vector3::new(0.0, 0.0, -5.0))));
+        let r = Ray3::new(Point3::new(0.0, 2.0, -5.0), Vector3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = instance.hit(&r, 0.001, 100.0).unwrap();
+        assert!((rec.t - 1.5).abs() < 0.0001);
+        assert!((rec.p - Point3::new(0.0, 0.5, -5.0)).magnitude() < 0.0001);
+        assert!((rec.normal - Vector3::unit_y()).magnitude() < 0.0001);
+    }
+
+    #[test]
+    fn bounding_box_follows_the_transform() {
+        let instance = Instance::new(unit_sphere(), Transform::scale(2.0, 1.0, 1.0).then(&Transform::translation(Vector3::new(3.0, 0.0, 0.0))));
+        let bounding_box = instance.bounding_box(0.0, 0.0).unwrap();
+        assert_eq!(bounding_box.min, Point3::new(1.0, -1.0, -1.0));
+        assert_eq!(bounding_box.max, Point3::new(5.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn material_replaces_the_geometry_material() {
+        let instance = Instance::new(unit_sphere(), Transform::identity()).with_material(Material::Dielectric { ref_idx: 1.5 });
+        let r = Ray3::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0), 0.0);
+        match instance.hit(&r, 0.001, 100.0).unwrap().material {
+            Some(Material::Dielectric { ref_idx }) => assert_eq!(ref_idx, 1.5),
+            _ => panic!("expected the instance material"),
+        }
+    }
+
+    #[test]
+    fn moving_instances_are_hit_where_they_are_at_the_ray_time() {
+        let motion = AnimatedTransform::new(vec![
+            Keyframe { time: 0.0, translation: Vector3::new(0.0, 0.0, 0.0), rotation: Quaternion::one(), scale: Vector3::new(1.0, 1.0, 1.0), curve: Curve::Linear },
+            Keyframe { time: 1.0, translation: Vector3::new(4.0, 0.0, 0.0), rotation: Quaternion::one(), scale: Vector3::new(1.0, 1.0, 1.0), curve: Curve::Linear },
+        ]);
+        let instance = MovingInstance::new(unit_sphere(), motion);
+        let ray_at = |time: f32| Ray3::new(Point3::new(2.0, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0), time);
+        assert!(instance.hit(&ray_at(0.0), 0.001, 100.0).is_none());
+        assert!((instance.hit(&ray_at(0.5), 0.001, 100.0).unwrap().t - 4.0).abs() < 0.0001);
+        let bounding_box = instance.bounding_box(0.0, 1.0).unwrap();
+        assert_eq!((bounding_box.min.x, bounding_box.max.x), (-1.0, 5.0));
+    }
+}
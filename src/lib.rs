@@ -0,0 +1,59 @@
+extern crate cgmath;
+extern crate rand;
+extern crate image;
+extern crate num_cpus;
+extern crate noise;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate toml;
+
+#[macro_use]
+extern crate log;
+
+pub mod error;
+pub mod hitable;
+pub mod sphere;
+pub mod triangle;
+pub mod quad;
+pub mod cuboid;
+pub mod pyramid;
+pub mod ray;
+pub mod camera;
+pub mod scene;
+pub mod scenefile;
+pub mod material;
+pub mod raytracer;
+pub mod renderer;
+pub mod progress;
+pub mod stats;
+pub mod teapot;
+pub mod plane;
+pub mod aabb;
+pub mod bvh;
+pub mod texture;
+pub mod light;
+pub mod environment;
+pub mod output;
+pub mod aov;
+pub mod tile;
+pub mod checkpoint;
+pub mod adaptive;
+pub mod sampler;
+pub mod filter;
+pub mod film;
+pub mod obj;
+pub mod mesh;
+pub mod animation;
+pub mod transform;
+pub mod instance;
+
+pub mod randomutil;
+pub mod timeutil;
+
+pub use error::Error;
+pub use renderer::{
+    Render,
+    Renderer,
+    RenderSettings,
+};
@@ -0,0 +1,293 @@
+use sampler::Sampler;
+
+use cgmath::{
+    Point3,
+    Vector3,
+    InnerSpace,
+};
+
+use std::f32;
+use std::sync::Arc;
+
+pub struct LightSample {
+    pub direction: Vector3<f32>,
+    pub distance: f32,
+    pub pdf: f32,
+}
+
+/// An emitter which can be sampled directly, with probability densities measured in solid angle
+/// as seen from the point being lit.
+pub trait Light {
+    fn sample(&self, origin: Point3<f32>, sampler: &mut Sampler) -> Option<LightSample>;
+    fn pdf(&self, origin: Point3<f32>, direction: Vector3<f32>) -> f32;
+}
+
+fn orthonormal_basis(w: Vector3<f32>) -> (Vector3<f32>, Vector3<f32>) {
+    let a = if w.x.abs() > 0.9 {
+        Vector3::unit_y()
+    } else {
+        Vector3::unit_x()
+    };
+    let v = w.cross(a).normalize();
+    let u = w.cross(v);
+    (u, v)
+}
+
+// Sphere Light
+
+pub struct SphereLight {
+    center: Point3<f32>,
+    radius: f32,
+}
+
+impl SphereLight {
+    pub fn new(center: Point3<f32>, radius: f32) -> Self {
+        SphereLight { center, radius }
+    }
+
+    fn cos_theta_max(&self, origin: Point3<f32>) -> Option<f32> {
+        let distance_squared = (self.center - origin).magnitude2();
+        let radius_squared = self.radius * self.radius;
+        if distance_squared <= radius_squared {
+            None
+        } else {
+            Some((1f32 - radius_squared / distance_squared).sqrt())
+        }
+    }
+
+    fn distance_to_surface(&self, origin: Point3<f32>, direction: Vector3<f32>) -> Option<f32> {
+        let oc = origin - self.center;
+        let b = oc.dot(direction);
+        let c = oc.magnitude2() - self.radius * self.radius;
+        let discriminant = b * b - c;
+        if discriminant < 0f32 {
+            return None;
+        }
+        let t = -b - discriminant.sqrt();
+        if t > 0f32 {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+impl Light for SphereLight {
+    // samples directions uniformly within the cone the sphere subtends
+    fn sample(&self, origin: Point3<f32>, sampler: &mut Sampler) -> Option<LightSample> {
+        let cos_theta_max = self.cos_theta_max(origin)?;
+        let w = (self.center - origin).normalize();
+        let (u, v) = orthonormal_basis(w);
+
+        let (r1, r2) = sampler.next_2d();
+        let cos_theta = 1f32 + r1 * (cos_theta_max - 1f32);
+        let sin_theta = (1f32 - cos_theta * cos_theta).max(0f32).sqrt();
+        let phi = 2f32 * f32::consts::PI * r2;
+        let direction = (u * phi.cos() * sin_theta + v * phi.sin() * sin_theta + w * cos_theta).normalize();
+
+        // a direction on the very edge of the cone can miss the sphere through rounding
+        let distance = self.distance_to_surface(origin, direction)
+            .unwrap_or_else(|| (self.center - origin).dot(direction));
+
+        Some(LightSample {
+            direction,
+            distance,
+            pdf: 1f32 / (2f32 * f32::consts::PI * (1f32 - cos_theta_max)),
+        })
+    }
+
+    fn pdf(&self, origin: Point3<f32>, direction: Vector3<f32>) -> f32 {
+        match (self.cos_theta_max(origin), self.distance_to_surface(origin, direction.normalize())) {
+            (Some(cos_theta_max), Some(_)) => 1f32 / (2f32 * f32::consts::PI * (1f32 - cos_theta_max)),
+            _ => 0f32,
+        }
+    }
+}
+
+// Triangle Light
+
+pub struct TriangleLight {
+    vertices: [Point3<f32>; 3],
+    normal: Vector3<f32>,
+    area: f32,
+}
+
+impl TriangleLight {
+    pub fn new(vertices: [Point3<f32>; 3]) -> Self {
+        let cross = (vertices[1] - vertices[0]).cross(vertices[2] - vertices[0]);
+        TriangleLight {
+            vertices,
+            normal: cross.normalize(),
+            area: 0.5 * cross.magnitude(),
+        }
+    }
+
+    // converts the uniform area density of a point on the triangle to solid angle from the origin
+    fn solid_angle_pdf(&self, direction: Vector3<f32>, distance: f32) -> f32 {
+        let cosine = self.normal.dot(direction).abs();
+        if cosine < 0.00001 {
+            0f32
+        } else {
+            distance * distance / (cosine * self.area)
+        }
+    }
+
+    fn distance_to_surface(&self, origin: Point3<f32>, direction: Vector3<f32>) -> Option<f32> {
+        let e1 = self.vertices[1] - self.vertices[0];
+        let e2 = self.vertices[2] - self.vertices[0];
+        let pvec = direction.cross(e2);
+        let det = e1.dot(pvec);
+        if det > -0.00001 && det < 0.00001 {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let tvec = origin - self.vertices[0];
+        let u = tvec.dot(pvec) * inv_det;
+        if u < 0.0 || u > 1.0 {
+            return None;
+        }
+        let qvec = tvec.cross(e1);
+        let v = direction.dot(qvec) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = e2.dot(qvec) * inv_det;
+        if t > 0.00001 {
+            Some(t)
+        } else {
+            None
+        }
+    }
+}
+
+impl Light for TriangleLight {
+    fn sample(&self, origin: Point3<f32>, sampler: &mut Sampler) -> Option<LightSample> {
+        let (r1, r2) = sampler.next_2d();
+        let su = r1.sqrt();
+        let b0 = 1f32 - su;
+        let b1 = r2 * su;
+        let point = self.vertices[0] + b1 * (self.vertices[1] - self.vertices[0]) + (1f32 - b0 - b1) * (self.vertices[2] - self.vertices[0]);
+
+        let to_point = point - origin;
+        let distance = to_point.magnitude();
+        if distance < 0.00001 {
+            return None;
+        }
+        let direction = to_point / distance;
+        let pdf = self.solid_angle_pdf(direction, distance);
+        if pdf > 0f32 {
+            Some(LightSample { direction, distance, pdf })
+        } else {
+            None
+        }
+    }
+
+    fn pdf(&self, origin: Point3<f32>, direction: Vector3<f32>) -> f32 {
+        let direction = direction.normalize();
+        self.distance_to_surface(origin, direction)
+            .map_or(0f32, |distance| self.solid_angle_pdf(direction, distance))
+    }
+}
+
+// Light List
+
+// lights are shared so that the frames of an animation can share them
+#[derive(Clone)]
+pub struct LightList {
+    lights: Vec<Arc<Light + Send + Sync>>,
+}
+
+impl LightList {
+    pub fn new() -> Self {
+        LightList { lights: Vec::new() }
+    }
+
+    pub fn with_light<L>(mut self, light: L) -> Self where L: Light + Send + Sync + 'static {
+        self.lights.push(Arc::new(light));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// Samples a direction towards one of the lights, chosen uniformly. The returned pdf is that of
+    /// the whole list, so that it matches `pdf` for directions which pass through several lights.
+    pub fn sample(&self, origin: Point3<f32>, sampler: &mut Sampler) -> Option<LightSample> {
+        if self.lights.is_empty() {
+            return None;
+        }
+        let index = ((sampler.next_1d() * self.lights.len() as f32) as usize).min(self.lights.len() - 1);
+        self.lights[index].sample(origin, sampler).and_then(|sample| {
+            let pdf = self.pdf(origin, sample.direction);
+            if pdf > 0f32 {
+                Some(LightSample { pdf, ..sample })
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn pdf(&self, origin: Point3<f32>, direction: Vector3<f32>) -> f32 {
+        if self.lights.is_empty() {
+            0f32
+        } else {
+            self.lights.iter().map(|light| light.pdf(origin, direction)).sum::<f32>() / self.lights.len() as f32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sampler::RandomSampler;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() <= 0.001 * b.abs(), "{} != {}", a, b);
+    }
+
+    #[test]
+    fn sphere_samples_hit_the_sphere_with_matching_pdf() {
+        let light = SphereLight::new(Point3::new(0.0, 10.0, 0.0), 2.0);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let mut sampler = RandomSampler::new(0);
+        for i in 0..100 {
+            sampler.start_sample(0, i);
+            let sample = light.sample(origin, &mut sampler).unwrap();
+            let point = origin + sample.direction * sample.distance;
+            assert_close((point - Point3::new(0.0, 10.0, 0.0)).magnitude(), 2.0);
+            assert_close(light.pdf(origin, sample.direction), sample.pdf);
+        }
+    }
+
+    #[test]
+    fn sphere_light_cannot_be_sampled_from_inside() {
+        let light = SphereLight::new(Point3::new(0.0, 0.0, 0.0), 2.0);
+        assert!(light.sample(Point3::new(0.5, 0.0, 0.0), &mut RandomSampler::new(0)).is_none());
+    }
+
+    #[test]
+    fn triangle_samples_hit_the_triangle_with_matching_pdf() {
+        let light = TriangleLight::new([Point3::new(-1.0, 5.0, -1.0), Point3::new(1.0, 5.0, -1.0), Point3::new(0.0, 5.0, 1.0)]);
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let mut sampler = RandomSampler::new(0);
+        for i in 0..100 {
+            sampler.start_sample(0, i);
+            let sample = light.sample(origin, &mut sampler).unwrap();
+            let point = origin + sample.direction * sample.distance;
+            assert_close(point.y, 5.0);
+            assert_close(light.pdf(origin, sample.direction), sample.pdf);
+        }
+    }
+
+    #[test]
+    fn list_pdf_averages_lights() {
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let sphere = SphereLight::new(Point3::new(0.0, 10.0, 0.0), 2.0);
+        let expected = sphere.pdf(origin, Vector3::unit_y()) / 2.0;
+        let list = LightList::new()
+            .with_light(sphere)
+            .with_light(SphereLight::new(Point3::new(0.0, -10.0, 0.0), 2.0));
+        assert_close(list.pdf(origin, Vector3::unit_y()), expected);
+    }
+}
@@ -1,54 +1,126 @@
-extern crate cgmath;
-extern crate rand;
-extern crate image;
+extern crate raytracer;
 extern crate clap;
-extern crate num_cpus;
-extern crate noise;
-
-#[macro_use]
-extern crate log;
 extern crate stderrlog;
 
-pub mod hitable;
-pub mod sphere;
-pub mod triangle;
-pub mod quad;
-pub mod pyramid;
-pub mod ray;
-pub mod camera;
-pub mod scene;
-pub mod material;
-pub mod raytracer;
-pub mod teapot;
-pub mod plane;
-pub mod aabb;
-pub mod bvh;
-pub mod texture;
-
-pub mod randomutil;
-pub mod timeutil;
+use raytracer::{
+    Error,
+    RenderSettings,
+};
+use raytracer::error::Result;
+use raytracer::raytracer::{
+    trace,
+    ProgressOutput,
+    TraceOptions,
+};
+use raytracer::aov::Aov;
+use raytracer::animation::FrameRange;
+use raytracer::adaptive::AdaptiveSampling;
+use raytracer::sampler::SamplerKind;
+use raytracer::filter::{
+    Filter,
+    FilterKind,
+};
+use raytracer::output::{
+    Format,
+    ToneMap,
+    ToneMapOperator,
+};
 
 use clap::{
     Arg,
     App,
+    ArgMatches,
+};
+
+use std::{
+    fmt,
+    process,
 };
+use std::path::Path;
+use std::str::FromStr;
 
 fn main() {
+    if let Err(err) = run() {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+// the value of an argument, if it was given or has a default
+fn parse<T>(matches: &ArgMatches, name: &str) -> Result<Option<T>> where T: FromStr, T::Err: fmt::Display {
+    match matches.value_of(name) {
+        Some(value) => value.parse::<T>().map(Some).map_err(|err| Error::Argument {
+            name: name.to_string(),
+            value: value.to_string(),
+            message: err.to_string(),
+        }),
+        None => Ok(None),
+    }
+}
+
+// sizes which nothing could be rendered with
+fn parse_nonzero(matches: &ArgMatches, name: &str) -> Result<Option<u32>> {
+    match parse::<u32>(matches, name)? {
+        Some(0) => Err(Error::Argument {
+            name: name.to_string(),
+            value: "0".to_string(),
+            message: "must be greater than zero".to_string(),
+        }),
+        value => Ok(value),
+    }
+}
+
+fn run() -> Result<()> {
     let matches = App::new("Raytracer")
         .version("1.0")
         .arg(Arg::with_name("scene")
             .display_order(0)
             .short("s")
             .long("scene")
-            .possible_values(&["default", "random", "test", "teapot", "motionblur", "2spheres", "2perlinspheres"])
+            .possible_values(&["default", "random", "test", "teapot", "motionblur", "2spheres", "2perlinspheres", "cornell"])
             .default_value("default")
             .takes_value(true))
+        .arg(Arg::with_name("scene-file")
+            .display_order(0)
+            .short("f")
+            .long("scene-file")
+            .value_name("FILE")
+            .help("Loads the scene from a TOML scene description instead of --scene")
+            .takes_value(true))
         .arg(Arg::with_name("out")
             .display_order(1)
             .short("o")
             .long("out")
             .value_name("FILE")
             .takes_value(true))
+        .arg(Arg::with_name("format")
+            .display_order(1)
+            .long("format")
+            .possible_values(&["exr", "hdr", "pfm", "png", "ppm"])
+            .help("The output image format, inferred from the --out extension if not given. exr, hdr and pfm store unclamped linear radiance")
+            .takes_value(true))
+        .arg(Arg::with_name("tone-map")
+            .display_order(1)
+            .long("tone-map")
+            .possible_values(&["clamp", "reinhard", "aces"])
+            .default_value("clamp")
+            .help("How radiance is mapped into png and ppm output")
+            .takes_value(true))
+        .arg(Arg::with_name("exposure")
+            .display_order(1)
+            .long("exposure")
+            .default_value("0")
+            .value_name("STOPS")
+            .allow_hyphen_values(true)
+            .help("Scales radiance by 2^STOPS before tone mapping")
+            .takes_value(true))
+        .arg(Arg::with_name("aov")
+            .display_order(1)
+            .long("aov")
+            .possible_values(&["depth", "normal", "albedo", "id"])
+            .use_delimiter(true)
+            .help("Comma separated render passes to write alongside the image, named after it")
+            .takes_value(true))
         .arg(Arg::with_name("nx")
             .display_order(2)
             .short("x")
@@ -70,6 +142,76 @@ fn main() {
             .default_value("100")
             .value_name("UINT")
             .takes_value(true))
+        .arg(Arg::with_name("sampler")
+            .display_order(4)
+            .long("sampler")
+            .possible_values(&["random", "stratified", "halton", "sobol"])
+            .default_value("random")
+            .help("How the samples in each pixel are spread out. stratified, halton and sobol are less noisy than random")
+            .takes_value(true))
+        .arg(Arg::with_name("filter")
+            .display_order(4)
+            .long("filter")
+            .possible_values(&["box", "tent", "gaussian", "mitchell", "lanczos"])
+            .default_value("box")
+            .help("How each sample is weighted into the pixels around it")
+            .takes_value(true))
+        .arg(Arg::with_name("filter-radius")
+            .display_order(4)
+            .long("filter-radius")
+            .value_name("PIXELS")
+            .help("How far each sample reaches, from 0.5 for box up to 3 for lanczos by default")
+            .takes_value(true))
+        .arg(Arg::with_name("adaptive-threshold")
+            .display_order(4)
+            .long("adaptive-threshold")
+            .value_name("FLOAT")
+            .help("Stops sampling pixels whose noise relative to their brightness falls below this, such as 0.01")
+            .takes_value(true))
+        .arg(Arg::with_name("min-samples")
+            .display_order(4)
+            .long("min-samples")
+            .default_value("16")
+            .value_name("UINT")
+            .help("The samples per pixel taken before adaptive sampling may stop")
+            .takes_value(true))
+        .arg(Arg::with_name("sample-heatmap")
+            .display_order(4)
+            .long("sample-heatmap")
+            .help("Writes the number of samples taken for each pixel alongside the image")
+            .takes_value(false))
+        .arg(Arg::with_name("progressive")
+            .display_order(4)
+            .long("progressive")
+            .value_name("UINT")
+            .help("Takes samples in passes of this many per pixel, rewriting the image after each pass")
+            .takes_value(true))
+        .arg(Arg::with_name("checkpoint")
+            .display_order(4)
+            .long("checkpoint")
+            .value_name("FILE")
+            .help("Saves the samples taken so far to FILE after each pass, to carry on later with --resume")
+            .takes_value(true))
+        .arg(Arg::with_name("resume")
+            .display_order(4)
+            .long("resume")
+            .value_name("FILE")
+            .help("Carries on a render from a checkpoint, which must match the scene, seed, sampler, filter and resolution")
+            .takes_value(true))
+        .arg(Arg::with_name("frames")
+            .display_order(4)
+            .long("frames")
+            .value_name("START..END")
+            .help("Renders these frames of the scene's animation as numbered images, such as out_0001.png")
+            .conflicts_with_all(&["checkpoint", "resume"])
+            .takes_value(true))
+        .arg(Arg::with_name("fps")
+            .display_order(4)
+            .long("fps")
+            .value_name("FLOAT")
+            .help("Frames per second of scene time for --frames")
+            .default_value("24")
+            .takes_value(true))
         .arg(Arg::with_name("seed")
             .display_order(5)
             .short("e")
@@ -88,6 +230,42 @@ fn main() {
             .long("height")
             .value_name("UINT")
             .takes_value(true))
+        .arg(Arg::with_name("threads")
+            .display_order(7)
+            .short("t")
+            .long("threads")
+            .value_name("UINT")
+            .help("The number of render threads, one per CPU by default")
+            .takes_value(true))
+        .arg(Arg::with_name("tile-size")
+            .display_order(7)
+            .long("tile-size")
+            .default_value("16")
+            .value_name("UINT")
+            .help("The width and height in pixels of the tiles handed out to render threads")
+            .takes_value(true))
+        .arg(Arg::with_name("progress")
+            .display_order(7)
+            .long("progress")
+            .help("Shows a progress bar with the rays traced per second and the time left on stderr")
+            .takes_value(false))
+        .arg(Arg::with_name("progress-json")
+            .display_order(7)
+            .long("progress-json")
+            .conflicts_with("progress")
+            .help("Reports progress on stderr as newline delimited JSON events, every second and after each pass")
+            .takes_value(false))
+        .arg(Arg::with_name("stats")
+            .display_order(7)
+            .long("stats")
+            .help("Prints counts of the rays traced, intersection tests and path lengths to stderr after rendering")
+            .takes_value(false))
+        .arg(Arg::with_name("stats-json")
+            .display_order(7)
+            .long("stats-json")
+            .value_name("FILE")
+            .help("Writes the render statistics to FILE as JSON")
+            .takes_value(true))
         .arg(Arg::with_name("verbose")
             .display_order(7)
             .short("v")
@@ -96,14 +274,79 @@ fn main() {
         .get_matches();
 
     let verbose = matches.is_present("verbose");
-    let nx = matches.value_of("nx").unwrap().parse::<u32>().unwrap();
-    let ny = matches.value_of("ny").unwrap().parse::<u32>().unwrap();
-    let ns = matches.value_of("samples-per-pixel").unwrap().parse::<u32>().unwrap();
-    let width = matches.value_of("width").map_or(nx, |w| w.parse::<u32>().unwrap());
-    let height = matches.value_of("height").map_or(ny, |h| h.parse::<u32>().unwrap());
-    let scene = matches.value_of("scene");
-    let seed = matches.value_of("seed");
-    let out = matches.value_of("out");
+    let nx = parse_nonzero(&matches, "nx")?.unwrap();
+    let ny = parse_nonzero(&matches, "ny")?.unwrap();
+    let width = parse_nonzero(&matches, "width")?.unwrap_or(nx);
+    let height = parse_nonzero(&matches, "height")?.unwrap_or(ny);
+    let filter_kind = FilterKind::from_name(matches.value_of("filter").unwrap()).unwrap();
+    let filter = Filter {
+        kind: filter_kind,
+        radius: parse::<f32>(&matches, "filter-radius")?.unwrap_or(filter_kind.default_radius()),
+    };
+    if !filter.radius.is_finite() || filter.radius <= 0f32 {
+        return Err(Error::Argument {
+            name: "filter-radius".to_string(),
+            value: filter.radius.to_string(),
+            message: "must be a finite number greater than zero".to_string(),
+        });
+    }
+    let aovs: Vec<Aov> = matches.values_of("aov").map_or(Vec::new(), |values| values.map(|aov| Aov::from_name(aov).unwrap()).collect());
+
+    let mut settings = RenderSettings::new(width, height)
+        .with_sampling_resolution(nx, ny)
+        .with_samples_per_pixel(parse::<u32>(&matches, "samples-per-pixel")?.unwrap())
+        .with_sampler(SamplerKind::from_name(matches.value_of("sampler").unwrap()).unwrap())
+        .with_filter(filter)
+        .with_aovs(&aovs)
+        .with_tile_size(parse_nonzero(&matches, "tile-size")?.unwrap());
+    if let Some(threads) = parse::<usize>(&matches, "threads")? {
+        settings = settings.with_threads(threads);
+    }
+    if let Some(pass_samples) = parse_nonzero(&matches, "progressive")? {
+        settings = settings.with_pass_samples(pass_samples);
+    }
+    if let Some(threshold) = parse::<f32>(&matches, "adaptive-threshold")? {
+        settings = settings.with_adaptive_sampling(AdaptiveSampling {
+            threshold,
+            min_samples: parse::<u32>(&matches, "min-samples")?.unwrap(),
+        });
+    }
+
+    let fps = parse::<f32>(&matches, "fps")?.unwrap();
+    if fps.is_nan() || fps <= 0f32 {
+        return Err(Error::Argument {
+            name: "fps".to_string(),
+            value: fps.to_string(),
+            message: "must be greater than zero".to_string(),
+        });
+    }
+
+    let options = TraceOptions {
+        scene: matches.value_of("scene"),
+        scene_file: matches.value_of_os("scene-file").map(Path::new),
+        out: matches.value_of_os("out").map(Path::new),
+        format: matches.value_of("format").and_then(Format::from_name),
+        tone_map: ToneMap {
+            operator: matches.value_of("tone-map").and_then(ToneMapOperator::from_name).unwrap_or(ToneMapOperator::Clamp),
+            exposure: parse::<f32>(&matches, "exposure")?.unwrap(),
+        },
+        checkpoint_file: matches.value_of_os("checkpoint").map(Path::new),
+        resume: matches.value_of_os("resume").map(Path::new),
+        sample_heatmap: matches.is_present("sample-heatmap"),
+        frames: parse::<FrameRange>(&matches, "frames")?,
+        fps,
+        progress: if matches.is_present("progress-json") {
+            ProgressOutput::Json
+        } else if matches.is_present("progress") {
+            ProgressOutput::Bar
+        } else {
+            ProgressOutput::Hidden
+        },
+        stats: matches.is_present("stats"),
+        stats_json: matches.value_of_os("stats-json").map(Path::new),
+        seed: parse::<u64>(&matches, "seed")?,
+        verbose,
+    };
 
     stderrlog::new()
         .module(module_path!())
@@ -113,5 +356,5 @@ fn main() {
         .init()
         .unwrap();
 
-    raytracer::trace(nx, ny, width, height, ns, scene, out, seed, verbose);
+    trace(settings, &options)
 }
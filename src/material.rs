@@ -1,6 +1,7 @@
 use ray::Ray3;
 use hitable::HitRecord;
 use randomutil;
+use sampler::Sampler;
 use texture::Texture;
 
 use cgmath::{
@@ -10,21 +11,24 @@ use cgmath::{
 };
 use cgmath::num_traits::pow;
 
-pub fn scatter(material: &Material, r_in: &Ray3<f32>, hit_rec: &HitRecord) -> (Vector3<f32>, Ray3<f32>, bool) {
+use std::f32;
+
+pub fn scatter(material: &Material, r_in: &Ray3<f32>, hit_rec: &HitRecord, sampler: &mut Sampler) -> (Vector3<f32>, Ray3<f32>, bool) {
     match *material {
         Material::Metal { albedo, fuzz } => {
             let reflected = reflect(r_in.direction.normalize(), hit_rec.normal);
-            let scattered = Ray3::new(hit_rec.p, reflected + fuzz * randomutil::random_in_unit_sphere(), r_in.time);
+            let scattered = Ray3::new(hit_rec.p, reflected + fuzz * randomutil::random_in_unit_sphere(sampler), r_in.time);
             let should_scatter = scattered.direction.dot(hit_rec.normal) > 0.0;
             (albedo, scattered, should_scatter)
         }
         Material::Lambertian { albedo } => {
-            let target = hit_rec.p + hit_rec.normal + randomutil::random_in_unit_sphere();
-            (albedo, Ray3::new(hit_rec.p, target - hit_rec.p, r_in.time), true)
+            (albedo, Ray3::new(hit_rec.p, cosine_direction(hit_rec.normal, sampler), r_in.time), true)
         }
         Material::TexturedLambertian { ref texture } => {
-            let target = hit_rec.p + hit_rec.normal + randomutil::random_in_unit_sphere();
-            (texture.value(0f32, 0f32, hit_rec.p), Ray3::new(hit_rec.p, target - hit_rec.p, r_in.time), true)
+            (texture.value(hit_rec.u, hit_rec.v, hit_rec.p), Ray3::new(hit_rec.p, cosine_direction(hit_rec.normal, sampler), r_in.time), true)
+        }
+        Material::DiffuseLight { .. } => {
+            (Vector3::zero(), Ray3::new(hit_rec.p, r_in.direction, r_in.time), false)
         }
         Material::Dielectric { ref_idx } => {
             let reflected = reflect(r_in.direction, hit_rec.normal);
@@ -44,7 +48,7 @@ pub fn scatter(material: &Material, r_in: &Ray3<f32>, hit_rec: &HitRecord) -> (V
                 1.0
             };
 
-            if randomutil::unit_distribution() < reflect_prob {
+            if sampler.next_1d() < reflect_prob {
                 (attenuation, Ray3::new(hit_rec.p, reflected, r_in.time), true)
             } else {
                 (attenuation, Ray3::new(hit_rec.p, refracted, r_in.time), true)
@@ -53,6 +57,46 @@ pub fn scatter(material: &Material, r_in: &Ray3<f32>, hit_rec: &HitRecord) -> (V
     }
 }
 
+/// For diffuse materials, the reflectance towards `direction` including the cosine term, and the
+/// probability density with which `scatter` chooses that direction. Specular materials return
+/// `None` as sampling lights directly cannot help them.
+pub fn evaluate(material: &Material, hit_rec: &HitRecord, direction: Vector3<f32>) -> Option<(Vector3<f32>, f32)> {
+    let albedo = match *material {
+        Material::Lambertian { albedo } => albedo,
+        Material::TexturedLambertian { ref texture } => texture.value(hit_rec.u, hit_rec.v, hit_rec.p),
+        _ => return None,
+    };
+    let cosine = hit_rec.normal.normalize().dot(direction.normalize()).max(0.0);
+    Some((albedo * cosine * f32::consts::FRAC_1_PI, cosine * f32::consts::FRAC_1_PI))
+}
+
+/// The colour of a surface regardless of lighting, as used by albedo render passes. Glass is
+/// white and lights are their emission clamped to one.
+pub fn albedo(material: &Material, hit_rec: &HitRecord) -> Vector3<f32> {
+    match *material {
+        Material::Metal { albedo, .. } | Material::Lambertian { albedo } => albedo,
+        Material::TexturedLambertian { ref texture } => texture.value(hit_rec.u, hit_rec.v, hit_rec.p),
+        Material::Dielectric { .. } => Vector3::new(1.0, 1.0, 1.0),
+        Material::DiffuseLight { ref emit, .. } => {
+            let colour = emit.value(hit_rec.u, hit_rec.v, hit_rec.p);
+            Vector3::new(colour.x.min(1.0), colour.y.min(1.0), colour.z.min(1.0))
+        }
+    }
+}
+
+pub fn emitted(material: &Material, r_in: &Ray3<f32>, hit_rec: &HitRecord) -> Vector3<f32> {
+    match *material {
+        Material::DiffuseLight { ref emit, one_sided } => {
+            if one_sided && r_in.direction.dot(hit_rec.normal) > 0.0 {
+                Vector3::zero()
+            } else {
+                emit.value(hit_rec.u, hit_rec.v, hit_rec.p)
+            }
+        }
+        _ => Vector3::zero(),
+    }
+}
+
 #[derive(Clone)]
 pub enum Material {
     Metal {
@@ -68,6 +112,21 @@ pub enum Material {
     Dielectric {
         ref_idx: f32,
     },
+    DiffuseLight {
+        one_sided: bool,
+        emit: Box<Texture + Send + Sync>,
+    },
+}
+
+// a unit normal offset by a uniformly distributed unit vector is cosine distributed about the normal
+fn cosine_direction(normal: Vector3<f32>, sampler: &mut Sampler) -> Vector3<f32> {
+    let normal = normal.normalize();
+    let direction = normal + randomutil::random_unit_vector(sampler);
+    if direction.magnitude2() < 0.00001 {
+        normal
+    } else {
+        direction
+    }
 }
 
 fn reflect(v: Vector3<f32>, n: Vector3<f32>) -> Vector3<f32> {
@@ -94,6 +153,10 @@ fn schlick(cosine: f32, ref_idx: f32) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use texture::ConstantTexture;
+    use sampler::RandomSampler;
+
+    use cgmath::Point3;
 
     #[test]
     fn option_clones() {
@@ -103,4 +166,34 @@ mod tests {
             _ => assert!(false),
         }
     }
+
+    #[test]
+    fn one_sided_light_only_emits_from_the_front() {
+        let light = Material::DiffuseLight { emit: ConstantTexture::new(Vector3::new(4f32, 4f32, 4f32)), one_sided: true };
+        let rec = HitRecord::new(1f32, Point3::new(0f32, 0f32, 0f32), Vector3::unit_y(), Some(light.clone()));
+        let from_above = Ray3::new(Point3::new(0f32, 1f32, 0f32), -Vector3::unit_y(), 0f32);
+        let from_below = Ray3::new(Point3::new(0f32, -1f32, 0f32), Vector3::unit_y(), 0f32);
+        assert_eq!(emitted(&light, &from_above, &rec), Vector3::new(4f32, 4f32, 4f32));
+        assert_eq!(emitted(&light, &from_below, &rec), Vector3::zero());
+    }
+
+    #[test]
+    fn lambertian_evaluates_cosine_weighted_reflectance() {
+        let material = Material::Lambertian { albedo: Vector3::new(0.5f32, 0.5f32, 0.5f32) };
+        let rec = HitRecord::new(1f32, Point3::new(0f32, 0f32, 0f32), Vector3::unit_y(), Some(material.clone()));
+        let (reflectance, pdf) = evaluate(&material, &rec, Vector3::unit_y()).unwrap();
+        assert_eq!(reflectance, Vector3::new(0.5f32, 0.5f32, 0.5f32) * f32::consts::FRAC_1_PI);
+        assert_eq!(pdf, f32::consts::FRAC_1_PI);
+        assert_eq!(evaluate(&material, &rec, -Vector3::unit_y()).unwrap().1, 0f32);
+        assert!(evaluate(&Material::Dielectric { ref_idx: 1.5 }, &rec, Vector3::unit_y()).is_none());
+    }
+
+    #[test]
+    fn lights_do_not_scatter() {
+        let light = Material::DiffuseLight { emit: ConstantTexture::new(Vector3::new(4f32, 4f32, 4f32)), one_sided: false };
+        let rec = HitRecord::new(1f32, Point3::new(0f32, 0f32, 0f32), Vector3::unit_y(), Some(light.clone()));
+        let r = Ray3::new(Point3::new(0f32, 1f32, 0f32), -Vector3::unit_y(), 0f32);
+        let (_, _, should_scatter) = scatter(&light, &r, &rec, &mut RandomSampler::new(0));
+        assert!(!should_scatter);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,152 @@
+use hitable::{
+    Hitable,
+    HitRecord,
+};
+use ray::Ray3;
+use aabb::AABB;
+use bvh::BVHNode;
+use material::Material;
+use triangle::{
+    Triangle,
+    NormalTriangle,
+};
+use obj::{
+    self,
+    ObjError,
+};
+
+use cgmath::{
+    Point3,
+    Vector3,
+    EuclideanSpace,
+    InnerSpace,
+    Quaternion,
+    Rotation,
+};
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub struct Mesh {
+    bvh: BVHNode,
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, ObjError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|error| ObjError::Io { file: path.display().to_string(), error })
+}
+
+impl Mesh {
+    /// Loads a Wavefront OBJ file, scaled, rotated and then moved to `centre` in the same way as
+    /// `Teapot::new`. Faces use `material` if given, otherwise the materials from the OBJ's MTL files.
+    pub fn load_obj(path: &str, centre: Point3<f32>, scale: f32, rotation: Quaternion<f32>, material: Option<Material>) -> Result<Self, ObjError> {
+        let path = Path::new(path);
+        let model = obj::parse_obj(open(path)?, &path.display().to_string())?;
+
+        let mut materials = HashMap::new();
+        if material.is_none() {
+            for library in &model.material_libraries {
+                let library_path = path.parent().unwrap_or(Path::new("")).join(library);
+                materials.extend(obj::parse_mtl(open(&library_path)?, &library_path.display().to_string())?);
+            }
+        }
+
+        let default_material = material.unwrap_or(Material::Lambertian { albedo: Vector3::new(0.5, 0.5, 0.5) });
+
+        let triangles = model.faces.iter().map(|face| {
+            let face_material = face.material.as_ref()
+                .and_then(|name| materials.get(name))
+                .unwrap_or(&default_material)
+                .clone();
+
+            // obj faces wind anticlockwise but triangles here expect clockwise winding, so the
+            // second and third vertices are swapped to keep normals facing outwards
+            let vertices = [face.vertices[0], face.vertices[2], face.vertices[1]];
+
+            let positions = [
+                rotation.rotate_point(model.positions[vertices[0].position] * scale) + centre.to_vec(),
+                rotation.rotate_point(model.positions[vertices[1].position] * scale) + centre.to_vec(),
+                rotation.rotate_point(model.positions[vertices[2].position] * scale) + centre.to_vec(),
+            ];
+
+            let uvs = match (vertices[0].uv, vertices[1].uv, vertices[2].uv) {
+                (Some(a), Some(b), Some(c)) => Some([model.uvs[a], model.uvs[b], model.uvs[c]]),
+                _ => None,
+            };
+
+            let triangle: Arc<Hitable + Send + Sync> = match (vertices[0].normal, vertices[1].normal, vertices[2].normal) {
+                (Some(a), Some(b), Some(c)) => {
+                    let normals = [
+                        rotation.rotate_vector(model.normals[a]).normalize(),
+                        rotation.rotate_vector(model.normals[b]).normalize(),
+                        rotation.rotate_vector(model.normals[c]).normalize(),
+                    ];
+                    let triangle = NormalTriangle::new(positions, normals, face_material);
+                    Arc::new(match uvs {
+                        Some(uvs) => triangle.with_uvs(uvs),
+                        None => triangle,
+                    })
+                }
+                _ => {
+                    let triangle = Triangle::new(positions, face_material);
+                    Arc::new(match uvs {
+                        Some(uvs) => triangle.with_uvs(uvs),
+                        None => triangle,
+                    })
+                }
+            };
+            triangle
+        }).collect();
+
+        // triangles are static so their bounding boxes do not depend on the time interval
+        Ok(Mesh {
+            bvh: BVHNode::new(triangles, 0f32, 0f32),
+        })
+    }
+}
+
+impl Hitable for Mesh {
+    fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        self.bvh.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
+        self.bvh.bounding_box(t0, t1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cgmath::One;
+
+    #[test]
+    fn loads_obj_with_materials_and_placement() {
+        let mesh = Mesh::load_obj("scenes/cube.obj", Point3::new(0.0, 2.0, 0.0), 2.0, Quaternion::one(), None).unwrap();
+        let bounding_box = mesh.bounding_box(0.0, 0.0).unwrap();
+        assert_eq!(bounding_box.min, Point3::new(-1.0, 1.0, -1.0));
+        assert_eq!(bounding_box.max, Point3::new(1.0, 3.0, 1.0));
+
+        let r = Ray3::new(Point3::new(0.0, 10.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 0.0);
+        let rec = mesh.hit(&r, 0.001, 100.0).unwrap();
+        assert_eq!(rec.p, Point3::new(0.0, 3.0, 0.0));
+        assert_eq!(rec.normal, Vector3::new(0.0, 1.0, 0.0));
+        match rec.material {
+            Some(Material::Lambertian { albedo }) => assert_eq!(albedo, Vector3::new(0.2, 0.4, 0.7)),
+            _ => panic!("expected the paint material on the top face"),
+        }
+    }
+
+    #[test]
+    fn missing_file_is_an_error() {
+        match Mesh::load_obj("scenes/missing.obj", Point3::new(0.0, 0.0, 0.0), 1.0, Quaternion::one(), None) {
+            Err(ObjError::Io { .. }) => {}
+            _ => panic!("expected an io error"),
+        }
+    }
+}
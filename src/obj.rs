@@ -0,0 +1,325 @@
+use material::Material;
+
+use cgmath::{
+    Point3,
+    Vector2,
+    Vector3,
+};
+
+use std::{
+    fmt,
+    io,
+};
+use std::io::BufRead;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        file: String,
+        error: io::Error,
+    },
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ObjError::Io { ref file, ref error } => write!(f, "{}: {}", file, error),
+            ObjError::Parse { ref file, line, ref message } => write!(f, "{}: line {}: {}", file, line, message),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ObjVertex {
+    pub position: usize,
+    pub uv: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ObjFace {
+    pub vertices: [ObjVertex; 3],
+    pub material: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ObjModel {
+    pub positions: Vec<Point3<f32>>,
+    pub uvs: Vec<Vector2<f32>>,
+    pub normals: Vec<Vector3<f32>>,
+    pub faces: Vec<ObjFace>,
+    pub material_libraries: Vec<String>,
+}
+
+struct LineParser<'a> {
+    file: &'a str,
+    line: usize,
+}
+
+impl<'a> LineParser<'a> {
+    fn error<T>(&self, message: String) -> Result<T, ObjError> {
+        Err(ObjError::Parse { file: self.file.to_string(), line: self.line, message })
+    }
+
+    fn floats(&self, args: &[&str], min: usize, max: usize) -> Result<Vec<f32>, ObjError> {
+        if args.len() < min || args.len() > max {
+            return self.error(format!("expected between {} and {} numbers but found {}", min, max, args.len()));
+        }
+        args.iter().map(|arg| {
+            arg.parse::<f32>().or_else(|_| self.error(format!("invalid number '{}'", arg)))
+        }).collect()
+    }
+
+    // obj indices are one-based, and negative indices count back from the most recent element
+    fn index(&self, arg: &str, count: usize) -> Result<usize, ObjError> {
+        let index = arg.parse::<i64>().or_else(|_| self.error(format!("invalid index '{}'", arg)))?;
+        let resolved = if index < 0 {
+            count as i64 + index
+        } else {
+            index - 1
+        };
+        if resolved < 0 || resolved >= count as i64 {
+            self.error(format!("index {} is out of range", index))
+        } else {
+            Ok(resolved as usize)
+        }
+    }
+
+    fn vertex(&self, arg: &str, model: &ObjModel) -> Result<ObjVertex, ObjError> {
+        let parts: Vec<&str> = arg.split('/').collect();
+        if parts.len() > 3 {
+            return self.error(format!("invalid face vertex '{}'", arg));
+        }
+        let position = self.index(parts[0], model.positions.len())?;
+        let uv = match parts.get(1) {
+            Some(part) if !part.is_empty() => Some(self.index(part, model.uvs.len())?),
+            _ => None,
+        };
+        let normal = match parts.get(2) {
+            Some(part) if !part.is_empty() => Some(self.index(part, model.normals.len())?),
+            _ => None,
+        };
+        Ok(ObjVertex { position, uv, normal })
+    }
+}
+
+fn read_lines<R: BufRead>(reader: R, file: &str) -> Result<Vec<String>, ObjError> {
+    reader.lines().collect::<Result<Vec<_>, _>>().map_err(|error| ObjError::Io { file: file.to_string(), error })
+}
+
+pub fn parse_obj<R: BufRead>(reader: R, file: &str) -> Result<ObjModel, ObjError> {
+    let mut model = ObjModel::default();
+    let mut material = None;
+    let lines = read_lines(reader, file)?;
+    let mut skipped = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let parser = LineParser { file, line: i + 1 };
+        let mut words = line.split('#').next().unwrap_or("").split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = words.collect();
+
+        match keyword {
+            "v" => {
+                let v = parser.floats(&args, 3, 4)?;
+                model.positions.push(Point3::new(v[0], v[1], v[2]));
+            }
+            "vt" => {
+                let vt = parser.floats(&args, 1, 3)?;
+                model.uvs.push(Vector2::new(vt[0], *vt.get(1).unwrap_or(&0f32)));
+            }
+            "vn" => {
+                let vn = parser.floats(&args, 3, 3)?;
+                model.normals.push(Vector3::new(vn[0], vn[1], vn[2]));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return parser.error(format!("face has {} vertices but needs at least 3", args.len()));
+                }
+                let vertices = args.iter().map(|arg| parser.vertex(arg, &model)).collect::<Result<Vec<_>, _>>()?;
+                // polygons are triangulated as a fan around their first vertex
+                for j in 1..vertices.len() - 1 {
+                    model.faces.push(ObjFace {
+                        vertices: [vertices[0], vertices[j], vertices[j + 1]],
+                        material: material.clone(),
+                    });
+                }
+            }
+            "usemtl" => material = args.first().map(|name| name.to_string()),
+            "mtllib" => model.material_libraries.extend(args.iter().map(|name| name.to_string())),
+            "o" | "g" | "s" | "l" | "p" => {}
+            // free-form geometry, display attributes and the like are valid but have no equivalent
+            _ => {
+                if !skipped.contains(&keyword) {
+                    warn!("{}: line {}: skipping unsupported statement '{}'", file, i + 1, keyword);
+                    skipped.push(keyword);
+                }
+            }
+        }
+    }
+
+    if model.faces.is_empty() {
+        Err(ObjError::Parse { file: file.to_string(), line: 0, message: "no faces found".to_string() })
+    } else {
+        Ok(model)
+    }
+}
+
+struct MtlEntry {
+    name: String,
+    diffuse: Vector3<f32>,
+    specular: Vector3<f32>,
+    specular_exponent: f32,
+    refractive_index: Option<f32>,
+    dissolve: f32,
+}
+
+impl MtlEntry {
+    fn new(name: String) -> Self {
+        MtlEntry {
+            name,
+            diffuse: Vector3::new(0.8, 0.8, 0.8),
+            specular: Vector3::new(0.0, 0.0, 0.0),
+            specular_exponent: 0.0,
+            refractive_index: None,
+            dissolve: 1.0,
+        }
+    }
+
+    // transparent materials become glass, materials which are more specular than diffuse become
+    // metal, with a fuzz that shrinks as the specular exponent sharpens, and everything else is matte
+    fn material(&self) -> Material {
+        let max = |c: Vector3<f32>| c.x.max(c.y).max(c.z);
+        if self.dissolve < 1.0 {
+            Material::Dielectric { ref_idx: self.refractive_index.unwrap_or(1.5) }
+        } else if max(self.specular) > max(self.diffuse) {
+            Material::Metal { albedo: self.specular, fuzz: (2f32 / (self.specular_exponent + 2f32)).sqrt().min(1f32) }
+        } else {
+            Material::Lambertian { albedo: self.diffuse }
+        }
+    }
+}
+
+pub fn parse_mtl<R: BufRead>(reader: R, file: &str) -> Result<HashMap<String, Material>, ObjError> {
+    let mut materials = HashMap::new();
+    let mut current: Option<MtlEntry> = None;
+
+    for (i, line) in read_lines(reader, file)?.iter().enumerate() {
+        let parser = LineParser { file, line: i + 1 };
+        let mut words = line.split('#').next().unwrap_or("").split_whitespace();
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+        let args: Vec<&str> = words.collect();
+
+        if keyword == "newmtl" {
+            if let Some(entry) = current.take() {
+                materials.insert(entry.name.clone(), entry.material());
+            }
+            match args.first() {
+                Some(name) => current = Some(MtlEntry::new(name.to_string())),
+                None => return parser.error("newmtl has no name".to_string()),
+            }
+            continue;
+        }
+
+        let entry = match current.as_mut() {
+            Some(entry) => entry,
+            None => return parser.error(format!("'{}' appears before newmtl", keyword)),
+        };
+
+        match keyword {
+            "Kd" => {
+                let kd = parser.floats(&args, 3, 3)?;
+                entry.diffuse = Vector3::new(kd[0], kd[1], kd[2]);
+            }
+            "Ks" => {
+                let ks = parser.floats(&args, 3, 3)?;
+                entry.specular = Vector3::new(ks[0], ks[1], ks[2]);
+            }
+            "Ns" => entry.specular_exponent = parser.floats(&args, 1, 1)?[0],
+            "Ni" => entry.refractive_index = Some(parser.floats(&args, 1, 1)?[0]),
+            "d" => entry.dissolve = parser.floats(&args, 1, 1)?[0],
+            "Tr" => entry.dissolve = 1f32 - parser.floats(&args, 1, 1)?[0],
+            // other statements (ambient colour, illumination model, texture maps) have no equivalent
+            _ => {}
+        }
+    }
+
+    if let Some(entry) = current.take() {
+        materials.insert(entry.name.clone(), entry.material());
+    }
+
+    Ok(materials)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_polygons_as_triangle_fans() {
+        let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\nusemtl red\nf 1/1/1 2/1/1 3/1/1 4/1/1\n";
+        let model = parse_obj(source.as_bytes(), "quad.obj").unwrap();
+        assert_eq!(model.positions.len(), 4);
+        assert_eq!(model.faces.len(), 2);
+        assert_eq!(model.faces[1].vertices[2], ObjVertex { position: 3, uv: Some(0), normal: Some(0) });
+        assert_eq!(model.faces[1].material, Some("red".to_string()));
+    }
+
+    #[test]
+    fn resolves_negative_indices() {
+        let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nf -3//  -2 -1\n";
+        let model = parse_obj(source.as_bytes(), "triangle.obj").unwrap();
+        let positions: Vec<usize> = model.faces[0].vertices.iter().map(|v| v.position).collect();
+        assert_eq!(positions, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn skips_unsupported_statements() {
+        let source = "v 0 0 0\nv 1 0 0\nv 1 1 0\nvp 0.5 0.5\ncstype bspline\ndeg 3\nf 1 2 3\n";
+        let model = parse_obj(source.as_bytes(), "curves.obj").unwrap();
+        assert_eq!(model.faces.len(), 1);
+        assert!(parse_obj("v 0 0 0\nv 1 0 0\nv 1 1 0\nvn 0 1\nf 1 2 3\n".as_bytes(), "broken.obj").is_err());
+    }
+
+    #[test]
+    fn out_of_range_index_is_an_error() {
+        let source = "v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+        match parse_obj(source.as_bytes(), "broken.obj") {
+            Err(ObjError::Parse { line, .. }) => assert_eq!(line, 3),
+            _ => panic!("expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn maps_mtl_entries_onto_materials() {
+        let source = "newmtl matte\nKd 0.5 0.1 0.1\n\nnewmtl mirror\nKd 0.1 0.1 0.1\nKs 0.9 0.9 0.9\nNs 1000\n\nnewmtl glass\nNi 1.33\nd 0.2\n";
+        let materials = parse_mtl(source.as_bytes(), "test.mtl").unwrap();
+        match materials["matte"] {
+            Material::Lambertian { albedo } => assert_eq!(albedo, Vector3::new(0.5, 0.1, 0.1)),
+            _ => panic!("expected lambertian"),
+        }
+        match materials["mirror"] {
+            Material::Metal { albedo, fuzz } => {
+                assert_eq!(albedo, Vector3::new(0.9, 0.9, 0.9));
+                assert!(fuzz < 0.05);
+            }
+            _ => panic!("expected metal"),
+        }
+        match materials["glass"] {
+            Material::Dielectric { ref_idx } => assert_eq!(ref_idx, 1.33),
+            _ => panic!("expected dielectric"),
+        }
+    }
+}
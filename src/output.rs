@@ -0,0 +1,346 @@
+use cgmath::Vector3;
+use image::{
+    self,
+    ImageBuffer,
+    Rgb,
+    RgbImage,
+};
+use image::png::PNGEncoder;
+use image::ppm::PPMEncoder;
+use image::hdr::HDREncoder;
+
+use std::io::{
+    self,
+    Write,
+};
+use std::path::Path;
+
+/// Linear radiance, the mean of the samples taken for each pixel.
+pub type RadianceImage = ImageBuffer<Rgb<f32>, Vec<f32>>;
+
+/// The brightness of a linear colour as perceived by the eye.
+pub fn luminance(colour: Vector3<f32>) -> f32 {
+    0.2126 * colour.x + 0.7152 * colour.y + 0.0722 * colour.z
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Format {
+    Exr,
+    Hdr,
+    Pfm,
+    Png,
+    Ppm,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "exr" => Some(Format::Exr),
+            "hdr" => Some(Format::Hdr),
+            "pfm" => Some(Format::Pfm),
+            "png" => Some(Format::Png),
+            "ppm" => Some(Format::Ppm),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_name)
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            Format::Exr => "exr",
+            Format::Hdr => "hdr",
+            Format::Pfm => "pfm",
+            Format::Png => "png",
+            Format::Ppm => "ppm",
+        }
+    }
+
+    /// Whether the format stores unclamped floating point radiance.
+    pub fn is_high_dynamic_range(&self) -> bool {
+        match *self {
+            Format::Exr | Format::Hdr | Format::Pfm => true,
+            Format::Png | Format::Ppm => false,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ToneMapOperator {
+    Clamp,
+    Reinhard,
+    Aces,
+}
+
+impl ToneMapOperator {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "clamp" => Some(ToneMapOperator::Clamp),
+            "reinhard" => Some(ToneMapOperator::Reinhard),
+            "aces" => Some(ToneMapOperator::Aces),
+            _ => None,
+        }
+    }
+
+    fn apply(&self, c: f32) -> f32 {
+        match *self {
+            ToneMapOperator::Clamp => c,
+            ToneMapOperator::Reinhard => c / (1f32 + c),
+            // Krzysztof Narkowicz's fit of the ACES filmic curve
+            ToneMapOperator::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+        }
+    }
+}
+
+/// How linear radiance becomes display values in 8-bit formats. High dynamic range formats are
+/// written without tone mapping or exposure so that they can be graded later.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ToneMap {
+    pub operator: ToneMapOperator,
+    // in stops, so each step doubles or halves the brightness
+    pub exposure: f32,
+}
+
+impl Default for ToneMap {
+    fn default() -> Self {
+        ToneMap {
+            operator: ToneMapOperator::Clamp,
+            exposure: 0f32,
+        }
+    }
+}
+
+fn srgb_encode(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1f32 / 2.4) - 0.055
+    }
+}
+
+impl ToneMap {
+    pub fn to_u8(&self, c: f32) -> u8 {
+        let exposed = c.max(0f32) * 2f32.powf(self.exposure);
+        let mapped = self.operator.apply(exposed).max(0f32).min(1f32);
+        (255.99 * srgb_encode(mapped)) as u8
+    }
+}
+
+pub fn write<W: Write>(img: &RadianceImage, format: Format, tone_map: &ToneMap, writer: &mut W) -> io::Result<()> {
+    match format {
+        Format::Exr => writer.write_all(&encode_exr(img)),
+        Format::Hdr => {
+            let pixels: Vec<Rgb<f32>> = img.pixels().cloned().collect();
+            HDREncoder::new(writer).encode(&pixels, img.width() as usize, img.height() as usize)
+                .map_err(|error| io::Error::new(io::ErrorKind::Other, error.to_string()))
+        }
+        Format::Pfm => writer.write_all(&encode_pfm(img)),
+        Format::Png | Format::Ppm => {
+            let display = img.iter().map(|&c| tone_map.to_u8(c)).collect();
+            write_u8(&ImageBuffer::from_raw(img.width(), img.height(), display).unwrap(), format, writer)
+        }
+    }
+}
+
+/// Writes an image which is already in display values, as PPM if asked and PNG otherwise.
+pub fn write_u8<W: Write>(img: &RgbImage, format: Format, writer: &mut W) -> io::Result<()> {
+    match format {
+        Format::Ppm => PPMEncoder::new(writer).encode(img, img.width(), img.height(), image::RGB(8)),
+        _ => PNGEncoder::new(writer).encode(img, img.width(), img.height(), image::RGB(8)),
+    }
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+fn push_u64(bytes: &mut Vec<u8>, value: u64) {
+    push_u32(bytes, value as u32);
+    push_u32(bytes, (value >> 32) as u32);
+}
+
+fn push_f32(bytes: &mut Vec<u8>, value: f32) {
+    push_u32(bytes, value.to_bits());
+}
+
+// portable float map: rows run from the bottom of the image up, and a negative scale marks
+// the floats as little endian
+fn encode_pfm(img: &RadianceImage) -> Vec<u8> {
+    let mut bytes = format!("PF\n{} {}\n-1.0\n", img.width(), img.height()).into_bytes();
+    for y in (0..img.height()).rev() {
+        for x in 0..img.width() {
+            for &channel in img.get_pixel(x, y).data.iter() {
+                push_f32(&mut bytes, channel);
+            }
+        }
+    }
+    bytes
+}
+
+fn push_exr_attribute(bytes: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    bytes.extend_from_slice(name.as_bytes());
+    bytes.push(0);
+    bytes.extend_from_slice(kind.as_bytes());
+    bytes.push(0);
+    push_u32(bytes, value.len() as u32);
+    bytes.extend_from_slice(value);
+}
+
+// a single part, uncompressed scanline OpenEXR file with 32-bit float channels, which every
+// reader supports without needing a compression library here
+fn encode_exr(img: &RadianceImage) -> Vec<u8> {
+    let (width, height) = (img.width(), img.height());
+    let mut bytes = vec![0x76, 0x2f, 0x31, 0x01];
+    push_u32(&mut bytes, 2);
+
+    // channels are stored in alphabetical order
+    let channel_order = [("B", 2), ("G", 1), ("R", 0)];
+    let mut channels = Vec::new();
+    for &(name, _) in channel_order.iter() {
+        channels.extend_from_slice(name.as_bytes());
+        channels.push(0);
+        push_u32(&mut channels, 2); // FLOAT
+        channels.extend_from_slice(&[0, 0, 0, 0]); // pLinear and reserved
+        push_u32(&mut channels, 1); // x sampling
+        push_u32(&mut channels, 1); // y sampling
+    }
+    channels.push(0);
+
+    let mut window = Vec::new();
+    for &value in [0, 0, width - 1, height - 1].iter() {
+        push_u32(&mut window, value);
+    }
+
+    let mut one = Vec::new();
+    push_f32(&mut one, 1f32);
+
+    push_exr_attribute(&mut bytes, "channels", "chlist", &channels);
+    push_exr_attribute(&mut bytes, "compression", "compression", &[0]);
+    push_exr_attribute(&mut bytes, "dataWindow", "box2i", &window);
+    push_exr_attribute(&mut bytes, "displayWindow", "box2i", &window);
+    push_exr_attribute(&mut bytes, "lineOrder", "lineOrder", &[0]);
+    push_exr_attribute(&mut bytes, "pixelAspectRatio", "float", &one);
+    push_exr_attribute(&mut bytes, "screenWindowCenter", "v2f", &[0; 8]);
+    push_exr_attribute(&mut bytes, "screenWindowWidth", "float", &one);
+    bytes.push(0);
+
+    // each scanline is its own chunk, located through a table of absolute offsets
+    let line_size = width as usize * channel_order.len() * 4;
+    let chunk_size = 8 + line_size;
+    let table_end = bytes.len() + height as usize * 8;
+    for y in 0..height as usize {
+        push_u64(&mut bytes, (table_end + y * chunk_size) as u64);
+    }
+
+    for y in 0..height {
+        push_u32(&mut bytes, y);
+        push_u32(&mut bytes, line_size as u32);
+        for &(_, index) in channel_order.iter() {
+            for x in 0..width {
+                push_f32(&mut bytes, img.get_pixel(x, y).data[index]);
+            }
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::hdr::HDRDecoder;
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        bytes[offset] as u32 | (bytes[offset + 1] as u32) << 8 | (bytes[offset + 2] as u32) << 16 | (bytes[offset + 3] as u32) << 24
+    }
+
+    fn read_f32(bytes: &[u8], offset: usize) -> f32 {
+        f32::from_bits(read_u32(bytes, offset))
+    }
+
+    fn gradient_image() -> RadianceImage {
+        ImageBuffer::from_fn(3, 2, |x, y| Rgb { data: [x as f32 * 4.0, y as f32, 0.25] })
+    }
+
+    #[test]
+    fn infers_format_from_extension() {
+        assert_eq!(Format::from_path(Path::new("render.EXR")), Some(Format::Exr));
+        assert_eq!(Format::from_path(Path::new("out/render.pfm")), Some(Format::Pfm));
+        assert_eq!(Format::from_path(Path::new("render.jpg")), None);
+        assert_eq!(Format::from_path(Path::new("render")), None);
+    }
+
+    #[test]
+    fn pfm_stores_unclamped_rows_bottom_up() {
+        let mut bytes = Vec::new();
+        write(&gradient_image(), Format::Pfm, &ToneMap::default(), &mut bytes).unwrap();
+        let header = "PF\n3 2\n-1.0\n";
+        assert!(bytes.starts_with(header.as_bytes()));
+        assert_eq!(bytes.len(), header.len() + 3 * 2 * 3 * 4);
+        // the first pixel written is the bottom left, then the next one along is twice as bright
+        assert_eq!(read_f32(&bytes, header.len() + 4), 1.0);
+        assert_eq!(read_f32(&bytes, header.len() + 2 * 3 * 4), 8.0);
+    }
+
+    #[test]
+    fn exr_offsets_point_at_scanlines() {
+        let img = gradient_image();
+        let mut bytes = Vec::new();
+        write(&img, Format::Exr, &ToneMap::default(), &mut bytes).unwrap();
+        assert_eq!(&bytes[0..4], &[0x76, 0x2f, 0x31, 0x01]);
+
+        let line_size = 3 * 3 * 4;
+        let first_chunk = bytes.len() - 2 * (8 + line_size);
+        assert_eq!(read_u32(&bytes, first_chunk - 16), first_chunk as u32);
+        assert_eq!(read_u32(&bytes, first_chunk - 8), (first_chunk + 8 + line_size) as u32);
+
+        // the second scanline's red channel comes after its blue and green channels
+        let second_chunk = first_chunk + 8 + line_size;
+        assert_eq!(read_u32(&bytes, second_chunk), 1);
+        assert_eq!(read_f32(&bytes, second_chunk + 8 + 2 * 3 * 4 + 2 * 4), 8.0);
+    }
+
+    #[test]
+    fn hdr_keeps_values_above_one() {
+        let mut bytes = Vec::new();
+        write(&gradient_image(), Format::Hdr, &ToneMap { operator: ToneMapOperator::Aces, exposure: -2.0 }, &mut bytes).unwrap();
+        let pixels = HDRDecoder::new(&bytes[..]).unwrap().read_image_hdr().unwrap();
+        assert_eq!(pixels[2].data[0], 8.0);
+    }
+
+    #[test]
+    fn png_saturates_bright_values() {
+        let mut bytes = Vec::new();
+        write(&gradient_image(), Format::Png, &ToneMap::default(), &mut bytes).unwrap();
+        let decoded = image::load_from_memory(&bytes).unwrap().to_rgb();
+        assert_eq!(decoded.get_pixel(2, 0).data, [255, 0, 137]);
+        assert_eq!(decoded.get_pixel(1, 1).data, [255, 255, 137]);
+    }
+
+    #[test]
+    fn srgb_encoding_matches_reference_values() {
+        let tone_map = ToneMap::default();
+        assert_eq!(tone_map.to_u8(0.0), 0);
+        assert_eq!(tone_map.to_u8(0.0031308), 10);
+        assert_eq!(tone_map.to_u8(0.214), 127);
+        assert_eq!(tone_map.to_u8(1.0), 255);
+    }
+
+    #[test]
+    fn exposure_is_measured_in_stops() {
+        let brighter = ToneMap { operator: ToneMapOperator::Clamp, exposure: 1.0 };
+        assert_eq!(brighter.to_u8(0.25), ToneMap::default().to_u8(0.5));
+    }
+
+    #[test]
+    fn operators_compress_highlights_without_clipping() {
+        for &operator in [ToneMapOperator::Reinhard, ToneMapOperator::Aces].iter() {
+            let tone_map = ToneMap { operator, exposure: 0.0 };
+            assert!(tone_map.to_u8(2.0) < tone_map.to_u8(8.0), "{:?}", operator);
+            assert!(tone_map.to_u8(0.5) < tone_map.to_u8(2.0), "{:?}", operator);
+        }
+    }
+}
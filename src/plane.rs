@@ -5,6 +5,10 @@ use hitable::{
 use ray::Ray3;
 use aabb::AABB;
 use material::Material;
+use stats::{
+    self,
+    Primitive,
+};
 
 use cgmath::{
     Vector3,
@@ -12,8 +16,6 @@ use cgmath::{
     InnerSpace,
 };
 
-use std::time::Instant;
-
 pub struct Plane {
     center: Point3<f32>,
     surface_normal: Vector3<f32>,
@@ -32,6 +34,7 @@ impl Plane {
 
 impl Hitable for Plane {
     fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        stats::count_hit_test(Primitive::Plane);
         let dot = self.surface_normal.dot(r.direction);
         if dot < 0.00001 {
             let p0r0 = self.center - r.origin;
@@ -48,7 +51,7 @@ impl Hitable for Plane {
         None
     }
 
-    fn bounding_box(&self, _: Instant, _: Instant) -> Option<AABB> {
-        unimplemented!()
+    fn bounding_box(&self, _: f32, _: f32) -> Option<AABB> {
+        None
     }
 }
\ No newline at end of file
@@ -0,0 +1,209 @@
+use std::io::{
+    self,
+    Write,
+};
+use std::sync::Mutex;
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+use std::time::Instant;
+
+use timeutil::TimeUtil;
+
+/// The work done by a render so far, updated by the render threads as they finish each tile
+/// and readable from any other thread while they work.
+pub struct Progress {
+    started: Mutex<Instant>,
+    pass: AtomicUsize,
+    tiles: AtomicUsize,
+    tiles_total: AtomicUsize,
+    samples: AtomicUsize,
+    samples_total: AtomicUsize,
+    rays: AtomicUsize,
+}
+
+/// A snapshot of a render's progress. Tiles are counted within the current pass, whereas
+/// samples and rays are counted over the whole render.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ProgressReport {
+    pub elapsed: f32,
+    pub pass: usize,
+    pub tiles: usize,
+    pub tiles_total: usize,
+    pub samples: usize,
+    // adaptive sampling may stop short of this
+    pub samples_total: usize,
+    pub rays: usize,
+}
+
+impl Progress {
+    pub fn new() -> Self {
+        Progress {
+            started: Mutex::new(Instant::now()),
+            pass: AtomicUsize::new(0),
+            tiles: AtomicUsize::new(0),
+            tiles_total: AtomicUsize::new(0),
+            samples: AtomicUsize::new(0),
+            samples_total: AtomicUsize::new(0),
+            rays: AtomicUsize::new(0),
+        }
+    }
+
+    /// Starts counting again for a render which will take at most `samples_total` samples.
+    pub fn begin(&self, samples_total: usize) {
+        *self.started.lock().unwrap() = Instant::now();
+        self.pass.store(0, Ordering::Relaxed);
+        self.tiles.store(0, Ordering::Relaxed);
+        self.tiles_total.store(0, Ordering::Relaxed);
+        self.samples.store(0, Ordering::Relaxed);
+        self.samples_total.store(samples_total, Ordering::Relaxed);
+        self.rays.store(0, Ordering::Relaxed);
+    }
+
+    pub fn begin_pass(&self, tiles_total: usize) {
+        self.pass.fetch_add(1, Ordering::Relaxed);
+        self.tiles.store(0, Ordering::Relaxed);
+        self.tiles_total.store(tiles_total, Ordering::Relaxed);
+    }
+
+    pub fn finish_tile(&self, samples: usize, rays: usize) {
+        self.samples.fetch_add(samples, Ordering::Relaxed);
+        self.rays.fetch_add(rays, Ordering::Relaxed);
+        self.tiles.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn report(&self) -> ProgressReport {
+        let elapsed = self.started.lock().unwrap().elapsed();
+        ProgressReport {
+            elapsed: elapsed.as_seconds(),
+            pass: self.pass.load(Ordering::Relaxed),
+            tiles: self.tiles.load(Ordering::Relaxed),
+            tiles_total: self.tiles_total.load(Ordering::Relaxed),
+            samples: self.samples.load(Ordering::Relaxed),
+            samples_total: self.samples_total.load(Ordering::Relaxed),
+            rays: self.rays.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress::new()
+    }
+}
+
+impl ProgressReport {
+    pub fn fraction(&self) -> f32 {
+        if self.samples_total == 0 {
+            1f32
+        } else {
+            (self.samples as f32 / self.samples_total as f32).min(1f32)
+        }
+    }
+
+    pub fn rays_per_second(&self) -> f32 {
+        if self.elapsed > 0f32 {
+            self.rays as f32 / self.elapsed
+        } else {
+            0f32
+        }
+    }
+
+    /// The seconds left at the rate samples have been taken so far, once any have been.
+    pub fn eta(&self) -> Option<f32> {
+        if self.samples == 0 {
+            None
+        } else {
+            Some((self.samples_total.saturating_sub(self.samples)) as f32 * self.elapsed / self.samples as f32)
+        }
+    }
+
+    /// Overwrites the current line with a bar such as
+    /// `[=====>    ]  50.0%  pass 2  tile 12/40  1.3M rays/s  ETA 0:42`.
+    pub fn write_bar<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let width = 30;
+        let filled = (self.fraction() * width as f32) as usize;
+        let bar: String = (0..width).map(|i| if i < filled { '=' } else if i == filled { '>' } else { ' ' }).collect();
+        let eta = self.eta().map_or("--:--".to_string(), format_duration);
+        write!(writer, "\r[{}] {:5.1}%  pass {}  tile {}/{}  {} rays/s  ETA {} ",
+               bar, self.fraction() * 100f32, self.pass, self.tiles, self.tiles_total, format_count(self.rays_per_second()), eta)?;
+        writer.flush()
+    }
+
+    /// Writes the report as a single line of JSON, with `event` saying what prompted it.
+    pub fn write_json<W: Write>(&self, event: &str, writer: &mut W) -> io::Result<()> {
+        write!(writer, "{{\"event\":\"{}\",\"elapsed\":{:.3},\"fraction\":{:.4},\"pass\":{},\"tiles\":{},\"tiles_total\":{},\"samples\":{},\"samples_total\":{},\"rays\":{},\"rays_per_second\":{:.0}",
+               event, self.elapsed, self.fraction(), self.pass, self.tiles, self.tiles_total, self.samples, self.samples_total, self.rays, self.rays_per_second())?;
+        match self.eta() {
+            Some(eta) => writeln!(writer, ",\"eta\":{:.1}}}", eta)?,
+            None => writeln!(writer, ",\"eta\":null}}")?,
+        }
+        writer.flush()
+    }
+}
+
+fn format_duration(seconds: f32) -> String {
+    let seconds = seconds.round() as u64;
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn format_count(count: f32) -> String {
+    if count >= 1_000_000f32 {
+        format!("{:.1}M", count / 1_000_000f32)
+    } else if count >= 1_000f32 {
+        format!("{:.1}k", count / 1_000f32)
+    } else {
+        format!("{:.0}", count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> ProgressReport {
+        ProgressReport { elapsed: 10.0, pass: 2, tiles: 3, tiles_total: 8, samples: 250, samples_total: 1000, rays: 2_500_000 }
+    }
+
+    #[test]
+    fn estimates_the_time_left() {
+        let report = report();
+        assert_eq!(report.fraction(), 0.25);
+        assert_eq!(report.rays_per_second(), 250_000.0);
+        assert_eq!(report.eta(), Some(30.0));
+        assert_eq!(ProgressReport { samples: 0, ..report }.eta(), None);
+    }
+
+    #[test]
+    fn counts_finished_tiles() {
+        let progress = Progress::new();
+        progress.begin(100);
+        progress.begin_pass(4);
+        progress.finish_tile(25, 60);
+        progress.finish_tile(25, 40);
+        let report = progress.report();
+        assert_eq!((report.pass, report.tiles, report.tiles_total), (1, 2, 4));
+        assert_eq!((report.samples, report.rays), (50, 100));
+    }
+
+    #[test]
+    fn writes_one_json_object_per_line() {
+        let mut bytes = Vec::new();
+        report().write_json("progress", &mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(),
+                   "{\"event\":\"progress\",\"elapsed\":10.000,\"fraction\":0.2500,\"pass\":2,\"tiles\":3,\"tiles_total\":8,\"samples\":250,\"samples_total\":1000,\"rays\":2500000,\"rays_per_second\":250000,\"eta\":30.0}\n");
+    }
+
+    #[test]
+    fn formats_durations_and_rates() {
+        assert_eq!(format_duration(42.4), "0:42");
+        assert_eq!(format_duration(3725.0), "1:02:05");
+        assert_eq!(format_count(1_260_000.0), "1.3M");
+        assert_eq!(format_count(950.0), "950");
+    }
+}
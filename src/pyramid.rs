@@ -17,8 +17,6 @@ use cgmath::{
     One,
 };
 
-use std::time::Instant;
-
 pub struct Pyramid {
     hitable_list: HitableList,
 }
@@ -49,7 +47,7 @@ impl Hitable for Pyramid {
         self.hitable_list.hit(r, t_min, t_max)
     }
 
-    fn bounding_box(&self, t0: Instant, t1: Instant) -> Option<AABB> {
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
         self.hitable_list.bounding_box(t0, t1)
     }
 }
\ No newline at end of file
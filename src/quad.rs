@@ -14,8 +14,6 @@ use cgmath::{
     Rotation,
 };
 
-use std::time::Instant;
-
 pub struct Quad {
     hitable_list: HitableList,
 }
@@ -35,7 +33,7 @@ impl Hitable for Quad {
         self.hitable_list.hit(r, t_min, t_max)
     }
 
-    fn bounding_box(&self, t0: Instant, t1: Instant) -> Option<AABB> {
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
         self.hitable_list.bounding_box(t0, t1)
     }
 }
\ No newline at end of file
@@ -2,14 +2,12 @@ use rand::{
     Rng,
     SeedableRng,
     StdRng,
-    thread_rng,
 };
 use rand::distributions::range::SampleRange;
-use cgmath::{
-    Vector3,
-    InnerSpace,
-};
+use sampler::Sampler;
+use cgmath::Vector3;
 
+use std::f32;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -43,24 +41,53 @@ pub fn seedable_unit_distribution() -> f32 {
     thread_seedable_rng().gen_range(0.0, 1.0)
 }
 
-pub fn unit_distribution() -> f32 {
-    thread_rng().gen_range(0.0, 1.0)
+// the samplers' numbers are mapped directly onto each shape rather than rejected until they fit,
+// so every sample takes the same number of dimensions and keeps its stratification
+
+pub fn random_in_unit_sphere(sampler: &mut Sampler) -> Vector3<f32> {
+    let direction = random_unit_vector(sampler);
+    direction * sampler.next_1d().cbrt()
+}
+
+pub fn random_unit_vector(sampler: &mut Sampler) -> Vector3<f32> {
+    let (u, v) = sampler.next_2d();
+    let z = 1.0 - 2.0 * u;
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    let phi = 2.0 * f32::consts::PI * v;
+    Vector3::new(r * phi.cos(), r * phi.sin(), z)
 }
 
-pub fn random_in_unit_sphere() -> Vector3<f32> {
-    let mut p: Vector3<f32>;
-    loop {
-        p = 2.0 * Vector3::new(unit_distribution(), unit_distribution(), unit_distribution()) - Vector3::new(1.0, 1.0, 1.0);
-        if p.magnitude2() >= 1.0 { break }
+// Shirley and Chiu's concentric mapping, which keeps neighbouring samples close together on the disk
+pub fn random_in_unit_disk(sampler: &mut Sampler) -> Vector3<f32> {
+    let (u, v) = sampler.next_2d();
+    let (a, b) = (2.0 * u - 1.0, 2.0 * v - 1.0);
+    if a == 0.0 && b == 0.0 {
+        return Vector3::new(0.0, 0.0, 0.0);
     }
-    p
+    let (r, theta) = if a.abs() > b.abs() {
+        (a, f32::consts::FRAC_PI_4 * (b / a))
+    } else {
+        (b, f32::consts::FRAC_PI_2 - f32::consts::FRAC_PI_4 * (a / b))
+    };
+    Vector3::new(r * theta.cos(), r * theta.sin(), 0.0)
 }
 
-pub fn random_in_unit_disk() -> Vector3<f32> {
-    let mut p: Vector3<f32>;
-    loop {
-        p = 2.0 * Vector3::new(unit_distribution(), unit_distribution(), 0.0) - Vector3::new(1.0, 1.0, 0.0);
-        if p.dot(p) >= 1.0 { break };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sampler::RandomSampler;
+
+    use cgmath::InnerSpace;
+
+    #[test]
+    fn points_fall_within_their_shapes() {
+        let mut sampler = RandomSampler::new(1);
+        for sample in 0..1000 {
+            sampler.start_sample(0, sample);
+            assert!(random_in_unit_sphere(&mut sampler).magnitude() <= 1.0);
+            assert!((random_unit_vector(&mut sampler).magnitude() - 1.0).abs() < 0.0001);
+            let p = random_in_unit_disk(&mut sampler);
+            assert!(p.magnitude() <= 1.0001 && p.z == 0.0);
+        }
     }
-    p
 }
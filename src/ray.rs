@@ -4,16 +4,16 @@ use cgmath::{
     BaseNum,
     EuclideanSpace,
 };
-use std::time::Instant;
 
 pub struct Ray3<T> where T: BaseNum {
     pub origin: Point3<T>,
     pub direction: Vector3<T>,
-    pub time: Instant,
+    // seconds of scene time
+    pub time: f32,
 }
 
 impl<T> Ray3<T> where T: BaseNum {
-    pub fn new(origin: Point3<T>, direction: Vector3<T>, time: Instant) -> Self {
+    pub fn new(origin: Point3<T>, direction: Vector3<T>, time: f32) -> Self {
         Ray3 { origin, direction, time }
     }
 
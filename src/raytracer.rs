@@ -1,197 +1,335 @@
-use ray::Ray3;
 use scene;
+use scenefile;
+use error::{
+    Error,
+    Result,
+};
 use randomutil;
-use material;
-use hitable::Hitable;
-
-use cgmath::{
-    Vector3,
-    InnerSpace,
-    Zero,
-    ElementWise,
+use renderer::{
+    Render,
+    Renderer,
+    RenderSettings,
+    DEFAULT_PASS_SAMPLES,
+};
+use adaptive;
+use progress::Progress;
+use checkpoint::Checkpoint;
+use timeutil::TimeUtil;
+use stats::RenderStats;
+use animation::{
+    self,
+    FrameRange,
 };
+use aov;
+use output::{
+    self,
+    Format,
+    ToneMap,
+};
+
 use std::{
     io,
     path,
     fs,
     env,
-    f32,
     thread,
 };
-use std::sync::{
-    Arc,
-    Mutex,
-};
 use std::io::Write;
+use std::sync::Arc;
+use std::sync::atomic::{
+    AtomicBool,
+    Ordering,
+};
+use std::time::Duration;
 use rand::{
     Rng,
     thread_rng,
 };
 use std::time::Instant;
-use image;
-use num_cpus;
-
-pub fn trace(nx: u32,
-             ny: u32,
-             width: u32,
-             height: u32,
-             ns: u32,
-             scene: Option<&str>,
-             out: Option<&str>,
-             seed: Option<&str>,
-             verbose: bool) {
+
+/// How the progress of a render is shown on stderr while it runs.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ProgressOutput {
+    Hidden,
+    Bar,
+    // newline delimited JSON events, for other programs to follow
+    Json,
+}
+
+/// What to render and where the command line wants it written, alongside the settings for
+/// rendering it.
+pub struct TraceOptions<'a> {
+    pub scene: Option<&'a str>,
+    pub scene_file: Option<&'a path::Path>,
+    pub out: Option<&'a path::Path>,
+    pub format: Option<Format>,
+    pub tone_map: ToneMap,
+    pub checkpoint_file: Option<&'a path::Path>,
+    pub resume: Option<&'a path::Path>,
+    pub sample_heatmap: bool,
+    // renders these frames at `fps` frames per second instead of a single image
+    pub frames: Option<FrameRange>,
+    pub fps: f32,
+    pub progress: ProgressOutput,
+    // a report of the work done is printed to stderr, or written as JSON to a file
+    pub stats: bool,
+    pub stats_json: Option<&'a path::Path>,
+    pub seed: Option<u64>,
+    pub verbose: bool,
+}
+
+/// Renders a scene to disk, or each of the frames of an animation of it, printing the paths of
+/// the images it wrote.
+pub fn trace(settings: RenderSettings, options: &TraceOptions) -> Result<()> {
     let start = Instant::now();
+    let verbose = options.verbose;
+    let (nx, ny, ns) = (settings.nx, settings.ny, settings.samples_per_pixel);
     let aspect = nx as f32 / ny as f32;
-    let seed_val = seed.map(|s| s.parse::<usize>().unwrap()).unwrap_or(thread_rng().next_u32() as usize);
-
-    randomutil::thread_seedable_reseed(seed_val);
-
-    let (world, camera) = match scene {
-        Some("random") => scene::random_scene(aspect, false),
-        Some("motionblur") => scene::random_scene(aspect, true),
-        Some("default") => scene::default_scene(aspect),
-        Some("teapot") => scene::teapot_scene(aspect),
-        Some("test") => scene::test_scene(aspect),
-        Some("2spheres") => scene::two_spheres(aspect),
-        Some("2perlinspheres") => scene::two_perlin_spheres(aspect),
-        _ => {
-            if verbose {
-                info!("Unrecognised scene type, using default");
-            }
-            scene::default_scene(aspect)
-        }
+    // scene files are named by their path, which is only ever shown or compared as text
+    let scene_file_name = options.scene_file.map(|path| path.to_string_lossy());
+    let scene_name = scene_file_name.as_ref().map(|name| name.as_ref()).or(options.scene).unwrap_or("default");
+
+    // a resumed render carries on with the checkpoint's seed unless another is given, which
+    // is then refused along with any other difference from the checkpointed render
+    let resumed = match options.resume {
+        Some(resume_file) => Some(Checkpoint::load(resume_file).map_err(|error| Error::Checkpoint { path: resume_file.to_path_buf(), error })?),
+        None => None,
     };
+    let seed_val = options.seed
+        .or_else(|| resumed.as_ref().map(|checkpoint| checkpoint.seed))
+        .unwrap_or(thread_rng().next_u32() as u64);
+    if let (Some(resume_file), Some(checkpoint)) = (options.resume, resumed.as_ref()) {
+        settings.clone().with_seed(seed_val).check_matches(scene_name, checkpoint)
+            .map_err(|error| Error::Checkpoint { path: resume_file.to_path_buf(), error })?;
+    }
+
+    randomutil::thread_seedable_reseed(seed_val as usize);
+
+    let description = match options.scene_file {
+        Some(path) => scenefile::load(path).map_err(|error| Error::Scene { name: scene_name.to_string(), error })?,
+        None => scene::load(scene_name)?,
+    };
+
+    // everything which does not move is built once, however many frames are rendered
+    let animation = description.animate(aspect).map_err(|error| Error::Scene { name: scene_name.to_string(), error })?;
+
+    // checkpoints are saved after each pass, so these default to passes even if not progressive
+    let checkpoint_file = options.checkpoint_file.or(options.resume);
+    let mut settings = settings.with_seed(seed_val);
+    if options.stats || options.stats_json.is_some() {
+        settings = settings.with_intersection_stats(true);
+    }
+    if checkpoint_file.is_some() && settings.pass_samples.is_none() {
+        settings = settings.with_pass_samples(DEFAULT_PASS_SAMPLES);
+    }
 
     let world_created = Instant::now();
     if verbose {
         let diff = world_created.duration_since(start);
-        info!("Created world in {:.*} seconds", 2, diff.as_secs() as f32 + diff.subsec_nanos() as f32 / 1_000_000_000f32);
+        info!("Created world in {:.*} seconds", 2, diff.as_seconds());
         info!("Starting ray trace with the following parameters");
         info!(" -    nx : {}", nx);
         info!(" -    ny : {}", ny);
         info!(" -    ns : {}", ns);
         info!(" -  seed : {}", seed_val);
-        info!(" - scene : {}", scene.unwrap_or("default"));
+        info!(" - threads : {}", settings.threads);
+        info!(" - tile size : {}", settings.tile_size);
+        info!(" - scene : {}", scene_name);
     }
 
-    let num = num_cpus::get();
-    let mut tasks = Vec::new();
-
-    let rows = (ny as f32 / num as f32) as u32;
-
-    let arc_world = Arc::new(world);
-    let arc_img = Arc::new(Mutex::new(image::ImageBuffer::new(nx, ny)));
+    // an explicit format wins over the output file's extension, and png remains the default
+    let output_format = options.format
+        .or_else(|| options.out.and_then(Format::from_path))
+        .unwrap_or(Format::Png);
 
-    for cpu in 0..num {
-        let inner_world = arc_world.clone();
-        let inner_img = arc_img.clone();
+    let output_path = match options.out {
+        Some(output_file) => output_file.to_path_buf(),
+        None => {
+            let temp_path = env::temp_dir().join(path::Path::new("raytracer"));
+            if !temp_path.exists() {
+                fs::create_dir(&temp_path).map_err(|error| Error::CreateDirectory { path: temp_path.clone(), error })?;
+            }
+            temp_path.join("out").with_extension(output_format.extension())
+        }
+    };
 
-        tasks.push(thread::spawn(move || {
-            let top = if cpu == num - 1 || (cpu as u32 + 1) * rows > ny {
-                ny
-            } else {
-                (cpu as u32 + 1) * rows
+    let mut stats = RenderStats::default();
+    let mut raytrace_seconds = 0f32;
+    let mut write_seconds = 0f32;
+    match options.frames {
+        None => {
+            let renderer = Renderer::from_scene(animation.frame(description.camera.shutter_open, f32::INFINITY), settings);
+            let mut render = match resumed {
+                Some(checkpoint) => renderer.resume(checkpoint),
+                None => renderer.start(),
             };
-
-            let mut sub_image = image::ImageBuffer::new(nx, ny);
-
-            for inverse_j in (cpu as u32 * rows)..top {
-                let j = ny - inverse_j - 1;
-                for i in 0..nx {
-                    let mut output_colour = Vector3::<f32>::zero();
-
-                    for _ in 0..ns {
-                        let u = (i as f32 + randomutil::unit_distribution()) / nx as f32;
-                        let v = (j as f32 + randomutil::unit_distribution()) / ny as f32;
-                        let r = camera.get_ray(u, v);
-
-                        output_colour += colour(&r, &*inner_world, 0)
-                    }
-
-                    output_colour /= ns as f32;
-                    output_colour = Vector3::new(
-                        output_colour.x.sqrt(),
-                        output_colour.y.sqrt(),
-                        output_colour.z.sqrt(),
-                    );
-
-                    sub_image.put_pixel(i, inverse_j - (cpu as u32 * rows), image::Rgb {
-                        data: [
-                            (255.99 * output_colour.x) as u8,
-                            (255.99 * output_colour.y) as u8,
-                            (255.99 * output_colour.z) as u8,
-                        ],
-                    });
-                }
+            render.checkpoint.scene = scene_name.to_string();
+            if verbose && render.checkpoint.accumulation.mean_samples() > 0f32 {
+                info!("Resuming with {:.1} of {} samples per pixel taken", render.checkpoint.accumulation.mean_samples(), ns);
             }
 
-            let mut img_data = inner_img.lock().unwrap();
+            let render_start = Instant::now();
+            let render = render_image(&renderer, render, &output_path, output_format, checkpoint_file, options)?;
+            raytrace_seconds += render_start.elapsed().as_seconds();
+            if verbose && renderer.settings().adaptive.is_some() {
+                info!("Took {:.1} samples per pixel on average", render.checkpoint.accumulation.mean_samples());
+            }
 
-            for i in 0..nx {
-                for inverse_j in (cpu as u32 * rows)..top {
-                    img_data.put_pixel(i, inverse_j, *sub_image.get_pixel(i, inverse_j - (cpu as u32 * rows)));
+            let write_start = Instant::now();
+            write_output(&renderer, &render, &output_path, output_format, options)?;
+            write_seconds += write_start.elapsed().as_seconds();
+            stats.add(&render.stats);
+        }
+        Some(frames) => {
+            // each frame gets a seed of its own, so that the noise is not the same in every frame
+            for frame in frames.frames() {
+                let time = frame as f32 / options.fps;
+                if verbose {
+                    info!("Rendering frame {} at {:.3} seconds", frame, time);
                 }
+                let frame_settings = settings.clone().with_seed(seed_val.wrapping_add(frame as u64));
+                let renderer = Renderer::from_scene(animation.frame(time, 1f32 / options.fps), frame_settings);
+                let frame_path = animation::frame_path(&output_path, frame);
+
+                let render_start = Instant::now();
+                let render = render_image(&renderer, renderer.start(), &frame_path, output_format, None, options)?;
+                raytrace_seconds += render_start.elapsed().as_seconds();
+
+                let write_start = Instant::now();
+                write_output(&renderer, &render, &frame_path, output_format, options)?;
+                write_seconds += write_start.elapsed().as_seconds();
+                stats.add(&render.stats);
             }
-        }));
+        }
     }
 
-    for task in tasks {
-        let _ = task.join();
+    if verbose {
+        info!("Raytrace complete in {:.*} seconds", 2, raytrace_seconds);
+    }
+    if options.stats {
+        let stderr = io::stderr();
+        stats.write_report(raytrace_seconds, &mut stderr.lock()).ok();
+    }
+    if let Some(stats_file) = options.stats_json {
+        write_atomically(stats_file, |stats_writer| stats.write_json(raytrace_seconds, stats_writer))?;
     }
-
-    let raytrace_complete = Instant::now();
     if verbose {
-        let diff = raytrace_complete.duration_since(world_created);
-        info!("Raytrace complete in {:.*} seconds", 2, diff.as_secs() as f32 + diff.subsec_nanos() as f32 / 1_000_000_000f32);
+        info!("Write complete in {:.*} seconds", 2, write_seconds);
+        info!("Program ran in {:.*} seconds", 2, start.elapsed().as_seconds());
     }
+    Ok(())
+}
 
-    let output_path = out
-        .map(|output_file| path::Path::new(&output_file).to_path_buf())
-        .unwrap_or_else(|| {
-            let temp_path = env::temp_dir().join(path::Path::new("raytracer"));
-            if !temp_path.exists() {
-                fs::create_dir(&temp_path).unwrap();
+// takes every sample for an image, rewriting it and the checkpoint, if there is one, after each
+// pass while it renders
+fn render_image(renderer: &Renderer, render: Render, output_path: &path::Path, output_format: Format, checkpoint_file: Option<&path::Path>, options: &TraceOptions) -> Result<Render> {
+    let progress = renderer.progress();
+    let reporting = Arc::new(AtomicBool::new(true));
+    let reporter = {
+        let (progress, reporting, output) = (progress.clone(), reporting.clone(), options.progress);
+        thread::spawn(move || report_progress(&progress, &reporting, output))
+    };
+
+    // progressive renders write the running mean after each pass, so that a render can be
+    // stopped as soon as it looks good
+    let render = renderer.render_from(render, |render| {
+        if options.progress == ProgressOutput::Json {
+            let stderr = io::stderr();
+            progress.report().write_json("pass", &mut stderr.lock()).ok();
+        }
+        if let Some(checkpoint_file) = checkpoint_file {
+            write_atomically(checkpoint_file, |file| render.checkpoint.write(file))?;
+        }
+        if !render.finished {
+            write_images(render, output_path, output_format, &options.tone_map)?;
+            if options.verbose {
+                info!("Wrote checkpoint after {:.1} of up to {} samples per pixel", render.checkpoint.accumulation.mean_samples(), renderer.settings().samples_per_pixel);
             }
-            temp_path.join("out.png")
-        });
+        }
+        Ok(())
+    });
+    reporting.store(false, Ordering::Relaxed);
+    reporter.join().unwrap();
+    render
+}
 
-    let ref mut output_file = fs::File::create(&output_path).unwrap();
+// writes the finished image and its passes, then prints their paths
+fn write_output(renderer: &Renderer, render: &Render, output_path: &path::Path, output_format: Format, options: &TraceOptions) -> Result<()> {
+    let mut image_paths = write_images(render, output_path, output_format, &options.tone_map)?;
+    if options.sample_heatmap {
+        let heatmap_path = output_path.with_extension(format!("samples.{}", output_format.extension()));
+        write_atomically(&heatmap_path, |heatmap_file| adaptive::write_heatmap(&render.checkpoint.accumulation, renderer.settings().samples_per_pixel, output_format, heatmap_file))?;
+        image_paths.push(heatmap_path);
+    }
 
-    if width != nx || height != ny {
-        image::ImageRgb8(arc_img.lock().unwrap().clone()).resize_exact(width, height, image::FilterType::CatmullRom).save(output_file, image::PNG).unwrap();
-    } else {
-        image::ImageRgb8(arc_img.lock().unwrap().clone()).save(output_file, image::PNG).unwrap();
+    if options.progress == ProgressOutput::Json {
+        let stderr = io::stderr();
+        renderer.progress().report().write_json("finished", &mut stderr.lock()).ok();
     }
 
-    let write_complete = Instant::now();
-    if verbose {
-        let diff = write_complete.duration_since(raytrace_complete);
-        let total_diff = write_complete.duration_since(start);
-        info!("Write complete in {:.*} seconds", 2, diff.as_secs() as f32 + diff.subsec_nanos() as f32 / 1_000_000_000f32);
-        info!("Program ran in {:.*} seconds", 2, total_diff.as_secs() as f32 + total_diff.subsec_nanos() as f32 / 1_000_000_000f32);
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    writeln!(stdout, "{}", output_path.display()).map_err(Error::Stdout)?;
+    for image_path in image_paths {
+        writeln!(stdout, "{}", image_path.display()).map_err(Error::Stdout)?;
     }
+    Ok(())
+}
 
-    writeln!(&mut io::stdout(), "{}", output_path.into_os_string().into_string().unwrap()).unwrap();
+// redraws the progress bar several times a second, or reports progress every second, until the
+// render stops. Progress is only ever shown on stderr, so failing to show it is not an error
+fn report_progress(progress: &Progress, reporting: &AtomicBool, output: ProgressOutput) {
+    let interval = match output {
+        ProgressOutput::Hidden => return,
+        ProgressOutput::Bar => 4,
+        ProgressOutput::Json => 20,
+    };
+    let mut ticks = 0;
+    while reporting.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(50));
+        ticks += 1;
+        if ticks % interval == 0 {
+            let stderr = io::stderr();
+            let report = progress.report();
+            match output {
+                ProgressOutput::Bar => report.write_bar(&mut stderr.lock()).ok(),
+                _ => report.write_json("progress", &mut stderr.lock()).ok(),
+            };
+        }
+    }
+    if output == ProgressOutput::Bar {
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        progress.report().write_bar(&mut stderr).and_then(|_| writeln!(stderr)).ok();
+    }
 }
 
-fn colour(ray: &Ray3<f32>, hitable: &Hitable, depth: u32) -> Vector3<f32> {
-        hitable.hit(ray, 0.001, f32::MAX).and_then(|rec| {
-            if rec.material.is_some() {
-                return rec.material.clone().map(|material| {
-                    if depth < 50 {
-                        let (attenuation, scattered, should_scatter) = material::scatter(&material, &ray, &rec);
-                        if should_scatter {
-                            return attenuation.mul_element_wise(colour(&scattered, hitable, depth + 1));
-                        }
-                    }
-                    Vector3::zero()
-                })
-            } else {
-                None
-            }
-        }).unwrap_or_else(|| {
-            let unit_direction = ray.direction.normalize();
-            let t = 0.5 * (unit_direction.y + 1.0);
-            (1.0 - t) * Vector3::new(1.0, 1.0, 1.0) + t * Vector3::new(0.5, 0.7, 1.0)
-        })
+// images are written to a temporary file which then replaces the target, so stopping a render
+// while it writes a checkpoint never leaves a broken image behind
+fn write_atomically<F>(path: &path::Path, write: F) -> Result<()> where F: FnOnce(&mut io::BufWriter<fs::File>) -> io::Result<()> {
+    let write_error = |error| Error::Write { path: path.to_path_buf(), error };
+    let mut partial_name = path.file_name()
+        .ok_or_else(|| write_error(io::Error::new(io::ErrorKind::InvalidInput, "not a file name")))?
+        .to_os_string();
+    partial_name.push(".partial");
+    let partial_path = path.with_file_name(partial_name);
+    {
+        let ref mut partial_file = io::BufWriter::new(fs::File::create(&partial_path).map_err(write_error)?);
+        write(partial_file).and_then(|_| partial_file.flush()).map_err(write_error)?;
+    }
+    fs::rename(&partial_path, path).map_err(write_error)
+}
+
+// writes the filtered samples taken so far along with any render passes, returning the paths
+// of the passes
+fn write_images(render: &Render, output_path: &path::Path, output_format: Format, tone_map: &ToneMap) -> Result<Vec<path::PathBuf>> {
+    let img = render.image();
+    write_atomically(output_path, |output_file| output::write(&img, output_format, tone_map, output_file))?;
+
+    // each pass is written next to the beauty image, so out.exr gains out.depth.exr and so on
+    render.aov_images().into_iter().map(|(aov, aov_img)| {
+        let aov_path = output_path.with_extension(format!("{}.{}", aov.name(), output_format.extension()));
+        write_atomically(&aov_path, |aov_file| aov::write(aov, &aov_img, output_format, aov_file))?;
+        Ok(aov_path)
+    }).collect()
 }
@@ -0,0 +1,638 @@
+use ray::Ray3;
+use material;
+use hitable::{
+    Hitable,
+    HitableList,
+    HitRecord,
+};
+use camera::Camera;
+use light::LightList;
+use environment::Environment;
+use scenefile::Scene;
+use tile::{
+    Tile,
+    TileQueue,
+};
+use film::{
+    self,
+    Film,
+};
+use filter::Filter;
+use sampler::{
+    Sampler,
+    SamplerKind,
+};
+use adaptive::{
+    self,
+    AdaptiveSampling,
+};
+use checkpoint::{
+    Accumulation,
+    Checkpoint,
+    CheckpointError,
+};
+use aov::{
+    Aov,
+    AovBuffer,
+};
+use output::RadianceImage;
+use progress::Progress;
+use stats::{
+    self,
+    PathEnd,
+    RenderStats,
+};
+
+use cgmath::{
+    Vector3,
+    Zero,
+    ElementWise,
+};
+use image;
+use num_cpus;
+
+use std::f32;
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+
+/// The samples per pixel in each pass when passes are needed but no pass size is given.
+pub const DEFAULT_PASS_SAMPLES: u32 = 16;
+
+fn kind_name<T: fmt::Debug>(kind: T) -> String {
+    format!("{:?}", kind).to_lowercase()
+}
+
+/// How an image is rendered: its resolution, how it is sampled and filtered and how the work is
+/// shared between threads. Settings start from defaults which the `with_` methods replace.
+#[derive(Clone, Debug)]
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    // samples are taken over an nx by ny grid, which is usually the same as the image
+    pub nx: u32,
+    pub ny: u32,
+    pub samples_per_pixel: u32,
+    pub pass_samples: Option<u32>,
+    pub adaptive: Option<AdaptiveSampling>,
+    pub aovs: Vec<Aov>,
+    pub threads: usize,
+    pub tile_size: u32,
+    pub sampler: SamplerKind,
+    pub filter: Filter,
+    pub seed: u64,
+    // rays and paths are always counted, but intersection tests only when asked for
+    pub intersection_stats: bool,
+}
+
+impl RenderSettings {
+    pub fn new(width: u32, height: u32) -> Self {
+        RenderSettings {
+            width,
+            height,
+            nx: width,
+            ny: height,
+            samples_per_pixel: 100,
+            pass_samples: None,
+            adaptive: None,
+            aovs: Vec::new(),
+            threads: num_cpus::get(),
+            tile_size: 16,
+            sampler: SamplerKind::Random,
+            filter: Filter::default(),
+            seed: 0,
+            intersection_stats: false,
+        }
+    }
+
+    /// Takes samples over an nx by ny grid, which are filtered onto the image at its own resolution.
+    pub fn with_sampling_resolution(mut self, nx: u32, ny: u32) -> Self {
+        self.nx = nx;
+        self.ny = ny;
+        self
+    }
+
+    pub fn with_samples_per_pixel(mut self, samples_per_pixel: u32) -> Self {
+        self.samples_per_pixel = samples_per_pixel;
+        self
+    }
+
+    /// Takes the samples in passes of this many per pixel, reporting the render after each one.
+    pub fn with_pass_samples(mut self, pass_samples: u32) -> Self {
+        self.pass_samples = Some(pass_samples);
+        self
+    }
+
+    pub fn with_adaptive_sampling(mut self, adaptive: AdaptiveSampling) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    pub fn with_aovs(mut self, aovs: &[Aov]) -> Self {
+        self.aovs = aovs.to_vec();
+        self
+    }
+
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    pub fn with_sampler(mut self, sampler: SamplerKind) -> Self {
+        self.sampler = sampler;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Counts the intersection tests, AABB tests and BVH nodes visited in the render's stats,
+    /// which slows rendering a little.
+    pub fn with_intersection_stats(mut self, intersection_stats: bool) -> Self {
+        self.intersection_stats = intersection_stats;
+        self
+    }
+
+    /// A checkpoint of a render of `scene` with these settings and no samples taken yet.
+    pub fn checkpoint(&self, scene: &str) -> Checkpoint {
+        Checkpoint {
+            scene: scene.to_string(),
+            seed: self.seed,
+            sampler: self.sampler,
+            filter: self.filter,
+            accumulation: Accumulation::new(self.nx, self.ny),
+            film: Film::new(self.width, self.height),
+        }
+    }
+
+    /// Refuses to carry on from a checkpoint of a render with different settings, which would
+    /// blend two different images.
+    pub fn check_matches(&self, scene: &str, checkpoint: &Checkpoint) -> Result<(), CheckpointError> {
+        let (checkpoint_nx, checkpoint_ny) = checkpoint.accumulation.sums.dimensions();
+        let (checkpoint_width, checkpoint_height) = checkpoint.film.sums.dimensions();
+        let mismatch = |message: String| Err(CheckpointError::Mismatch(message));
+        if checkpoint.scene != scene {
+            mismatch(format!("it renders scene '{}' rather than '{}'", checkpoint.scene, scene))
+        } else if checkpoint.seed != self.seed {
+            mismatch(format!("it was seeded with {} rather than {}", checkpoint.seed, self.seed))
+        } else if checkpoint.sampler != self.sampler {
+            mismatch(format!("it uses the {} sampler rather than {}", kind_name(checkpoint.sampler), kind_name(self.sampler)))
+        } else if checkpoint.filter != self.filter {
+            mismatch(format!("it uses a {} filter of radius {} rather than a {} filter of radius {}",
+                             kind_name(checkpoint.filter.kind), checkpoint.filter.radius, kind_name(self.filter.kind), self.filter.radius))
+        } else if (checkpoint_nx, checkpoint_ny) != (self.nx, self.ny) {
+            mismatch(format!("it is {}x{} rather than {}x{}", checkpoint_nx, checkpoint_ny, self.nx, self.ny))
+        } else if (checkpoint_width, checkpoint_height) != (self.width, self.height) {
+            mismatch(format!("its image is {}x{} rather than {}x{}", checkpoint_width, checkpoint_height, self.width, self.height))
+        } else {
+            Ok(())
+        }
+    }
+
+    // adaptive sampling decides which pixels need more samples between passes, so it takes
+    // its samples in passes even if no pass size is given
+    fn passes_of(&self) -> u32 {
+        let default = if self.adaptive.is_some() { DEFAULT_PASS_SAMPLES } else { self.samples_per_pixel };
+        self.pass_samples.unwrap_or(default).max(1)
+    }
+}
+
+/// A render in progress: the samples taken so far, which can be saved as a checkpoint, and any
+/// render passes.
+pub struct Render {
+    pub checkpoint: Checkpoint,
+    // render passes are not checkpointed, so after resuming they only gather new samples
+    pub aovs: AovBuffer,
+    // the work done since the render was started or resumed
+    pub stats: RenderStats,
+    pub finished: bool,
+}
+
+impl Render {
+    /// The filtered mean radiance of each pixel, unclamped.
+    pub fn image(&self) -> RadianceImage {
+        self.checkpoint.film.image()
+    }
+
+    /// The render passes, resampled to the image's resolution where that differs from the
+    /// resolution they were sampled at.
+    pub fn aov_images(&self) -> Vec<(Aov, RadianceImage)> {
+        let (width, height) = self.checkpoint.film.sums.dimensions();
+        self.aovs.images().into_iter().map(|(aov, img)| {
+            if img.dimensions() == (width, height) {
+                (aov, img)
+            } else {
+                // ids are not blended with their neighbours
+                let filter = if aov == Aov::Id { image::FilterType::Nearest } else { image::FilterType::CatmullRom };
+                (aov, image::imageops::resize(&img, width, height, filter))
+            }
+        }).collect()
+    }
+}
+
+// everything a thread rendered for one tile, kept apart until the pass is over
+struct TileRender {
+    tile: Tile,
+    accumulation: Accumulation,
+    aovs: AovBuffer,
+    film: Film,
+    film_x: i32,
+    film_y: i32,
+    stats: RenderStats,
+}
+
+/// Renders a world as seen through a camera into memory.
+///
+/// ```no_run
+/// # use raytracer::scene;
+/// # use raytracer::renderer::{Renderer, RenderSettings};
+/// let scene = scene::cornell_box().build(1.0).unwrap();
+/// let settings = RenderSettings::new(256, 256).with_samples_per_pixel(64);
+/// let image = Renderer::from_scene(scene, settings).render();
+/// ```
+pub struct Renderer {
+    world: Arc<HitableList>,
+    lights: Arc<LightList>,
+    environment: Arc<Environment>,
+    camera: Camera,
+    settings: RenderSettings,
+    progress: Arc<Progress>,
+}
+
+impl Renderer {
+    /// Gathers the world's bounded objects under a BVH over the camera's shutter interval. The
+    /// world is lit by the default sky until given other lights or another environment.
+    pub fn new(world: HitableList, camera: Camera, settings: RenderSettings) -> Self {
+        let (shutter_open, shutter_close) = camera.shutter_interval();
+        Renderer {
+            world: Arc::new(world.into_bvh(shutter_open, shutter_close)),
+            lights: Arc::new(LightList::new()),
+            environment: Arc::new(Environment::sky()),
+            camera,
+            settings,
+            progress: Arc::new(Progress::new()),
+        }
+    }
+
+    pub fn from_scene(scene: Scene, settings: RenderSettings) -> Self {
+        Renderer::new(scene.world, scene.camera, settings)
+            .with_lights(scene.lights)
+            .with_environment(scene.environment)
+    }
+
+    /// Emitters to sample directly from diffuse surfaces, as well as finding them by chance.
+    pub fn with_lights(mut self, lights: LightList) -> Self {
+        self.lights = Arc::new(lights);
+        self
+    }
+
+    pub fn with_environment(mut self, environment: Arc<Environment>) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    /// The progress of the current render, which can be watched from another thread.
+    pub fn progress(&self) -> Arc<Progress> {
+        self.progress.clone()
+    }
+
+    /// Renders the whole image, returning its unclamped radiance.
+    pub fn render(&self) -> RadianceImage {
+        match self.render_from(self.start(), |_| Ok::<(), ()>(())) {
+            Ok(render) => render.image(),
+            Err(()) => unreachable!(),
+        }
+    }
+
+    /// A render with no samples taken yet.
+    pub fn start(&self) -> Render {
+        self.resume(self.settings.checkpoint(""))
+    }
+
+    /// Carries on from a checkpoint, which must match these settings' seed, sampler, filter and
+    /// resolutions.
+    pub fn resume(&self, checkpoint: Checkpoint) -> Render {
+        Render {
+            checkpoint,
+            aovs: AovBuffer::new(&self.settings.aovs, self.settings.nx, self.settings.ny),
+            stats: RenderStats::default(),
+            finished: false,
+        }
+    }
+
+    /// Takes samples until every pixel has enough, calling `on_pass` with the render so far
+    /// after each pass, and returns the finished render. An error from `on_pass` stops the
+    /// render and is returned instead.
+    pub fn render_from<F, E>(&self, render: Render, mut on_pass: F) -> Result<Render, E> where F: FnMut(&Render) -> Result<(), E> {
+        let settings = &self.settings;
+        let _counting = if settings.intersection_stats { Some(stats::IntersectionCounting::start()) } else { None };
+        let mut render = render;
+        let pass_samples = settings.passes_of();
+        let mut plan = adaptive::plan(&render.checkpoint.accumulation, pass_samples, settings.samples_per_pixel, settings.adaptive.as_ref());
+        render.finished = plan.iter().all(|&samples| samples == 0);
+        self.progress.begin(render.checkpoint.accumulation.samples.iter().map(|&taken| settings.samples_per_pixel.saturating_sub(taken) as usize).sum());
+        while !render.finished {
+            let arc_plan = Arc::new(plan);
+            let arc_taken = Arc::new(render.checkpoint.accumulation.samples.clone());
+            let arc_tiles = Arc::new(TileQueue::new(settings.nx, settings.ny, settings.tile_size));
+            self.progress.begin_pass(arc_tiles.len());
+            let mut tasks = Vec::new();
+
+            for _ in 0..settings.threads.max(1) {
+                let inner_world = self.world.clone();
+                let inner_lights = self.lights.clone();
+                let inner_environment = self.environment.clone();
+                let inner_tiles = arc_tiles.clone();
+                let inner_plan = arc_plan.clone();
+                let inner_taken = arc_taken.clone();
+                let inner_settings = settings.clone();
+                let inner_progress = self.progress.clone();
+                let camera = self.camera;
+
+                tasks.push(thread::spawn(move || {
+                    let settings = inner_settings;
+                    let (nx, ny) = (settings.nx, settings.ny);
+                    let filter = settings.filter;
+                    let mut sampler = settings.sampler.build(settings.seed, settings.samples_per_pixel);
+                    // samples are taken over an nx by ny grid but filtered onto a film of the output
+                    // image's resolution, so both are measured in pixels of their own grid
+                    let (scale_x, scale_y) = (settings.width as f32 / nx as f32, settings.height as f32 / ny as f32);
+                    // when upscaling, the filter is stretched to span the gap between samples
+                    let stretch = (scale_x.max(1f32), scale_y.max(1f32));
+                    let mut tile_renders = Vec::new();
+                    while let Some(tile) = inner_tiles.take() {
+                        let mut tile_accumulation = Accumulation::new(tile.width, tile.height);
+                        let mut tile_aovs = AovBuffer::new(&settings.aovs, tile.width, tile.height);
+                        let (left, right) = film::reach(filter.radius * stretch.0, tile.x as f32 * scale_x, (tile.x + tile.width) as f32 * scale_x);
+                        let (top, bottom) = film::reach(filter.radius * stretch.1, tile.y as f32 * scale_y, (tile.y + tile.height) as f32 * scale_y);
+                        let mut tile_film = Film::new((right - left + 1) as u32, (bottom - top + 1) as u32);
+                        let mut tile_samples = 0;
+
+                        for y in 0..tile.height {
+                            for x in 0..tile.width {
+                                let i = tile.x + x;
+
+                                // samples are numbered across passes and resumed renders, and the
+                                // sampler derives every one from the seed, pixel and that number alone
+                                let pixel = ((tile.y + y) * nx + i) as usize;
+                                let taken = inner_taken[pixel];
+                                for sample in taken..taken + inner_plan[pixel] {
+                                    sampler.start_sample(pixel as u64, sample);
+                                    let (du, dv) = sampler.next_2d();
+                                    // from the top left, whereas the camera measures v from the bottom
+                                    let (film_x, film_y) = (i as f32 + du, (tile.y + y) as f32 + dv);
+                                    let u = film_x / nx as f32;
+                                    let v = (ny as f32 - film_y) / ny as f32;
+                                    let r = camera.get_ray(u, v, &mut *sampler);
+
+                                    let (sample_colour, first_hit) = camera_colour(&r, &*inner_world, &*inner_lights, &*inner_environment, &mut *sampler);
+                                    tile_aovs.add_sample(x, y, &r, first_hit.as_ref());
+                                    tile_accumulation.add_sample(x, y, sample_colour);
+                                    tile_film.add_stretched_sample(&filter, stretch, film_x * scale_x - left as f32, film_y * scale_y - top as f32, sample_colour);
+                                }
+                                tile_samples += inner_plan[pixel] as usize;
+                            }
+                        }
+                        let tile_stats = stats::take_thread_stats();
+                        inner_progress.finish_tile(tile_samples, tile_stats.rays() as usize);
+
+                        tile_renders.push(TileRender {
+                            tile,
+                            accumulation: tile_accumulation,
+                            aovs: tile_aovs,
+                            film: tile_film,
+                            film_x: left,
+                            film_y: top,
+                            stats: tile_stats,
+                        });
+                    }
+                    tile_renders
+                }));
+            }
+
+            // the films of neighbouring tiles overlap as far as the filter reaches, so tiles are
+            // added in the same order whichever threads rendered them to keep renders identical
+            let mut tile_renders: Vec<TileRender> = tasks.into_iter().flat_map(|task| task.join().unwrap()).collect();
+            tile_renders.sort_by_key(|tile_render| (tile_render.tile.y, tile_render.tile.x));
+            for tile_render in tile_renders {
+                let tile = tile_render.tile;
+                render.checkpoint.accumulation.add_from(&tile_render.accumulation, tile.x, tile.y);
+                render.checkpoint.film.add_from(&tile_render.film, tile_render.film_x, tile_render.film_y);
+                render.aovs.add_from(&tile_render.aovs, tile.x, tile.y);
+                render.stats.add(&tile_render.stats);
+            }
+
+            plan = adaptive::plan(&render.checkpoint.accumulation, pass_samples, settings.samples_per_pixel, settings.adaptive.as_ref());
+            render.finished = plan.iter().all(|&samples| samples == 0);
+            on_pass(&render)?;
+        }
+        Ok(render)
+    }
+}
+
+// power heuristic weight for combining a sample from one strategy with another
+fn mis_weight(pdf: f32, other_pdf: f32) -> f32 {
+    let pdf_squared = pdf * pdf;
+    pdf_squared / (pdf_squared + other_pdf * other_pdf)
+}
+
+// light arriving at a diffuse hit directly from a sampled light, weighted against the
+// chance of the material's own scattered ray finding that light
+fn direct_light(rec: &HitRecord, material: &material::Material, ray: &Ray3<f32>, hitable: &Hitable, lights: &LightList, environment: &Environment, sampler: &mut Sampler) -> Vector3<f32> {
+    lights.sample(rec.p, sampler).and_then(|sample| {
+        material::evaluate(material, rec, sample.direction).and_then(|(reflectance, scattering_pdf)| {
+            if reflectance == Vector3::zero() {
+                return None;
+            }
+            let shadow_ray = Ray3::new(rec.p, sample.direction, ray.time);
+            stats::count_shadow_ray();
+            let emitted = match hitable.hit(&shadow_ray, 0.001, sample.distance * 1.001) {
+                Some(light_rec) => {
+                    if light_rec.t < sample.distance * 0.999 {
+                        return None;
+                    }
+                    light_rec.material.as_ref().map(|light_material| material::emitted(light_material, &shadow_ray, &light_rec))
+                }
+                // samples of the environment are infinitely far away and lit only if nothing is in the way
+                None if sample.distance.is_infinite() => Some(environment.value(sample.direction)),
+                None => None,
+            };
+            emitted.map(|emitted| mis_weight(sample.pdf, scattering_pdf) * emitted.mul_element_wise(reflectance) / sample.pdf)
+        })
+    }).unwrap_or_else(Vector3::zero)
+}
+
+// the colour seen along a camera ray, along with what it hit first for the render passes
+fn camera_colour(ray: &Ray3<f32>, hitable: &Hitable, lights: &LightList, environment: &Environment, sampler: &mut Sampler) -> (Vector3<f32>, Option<HitRecord>) {
+    stats::count_ray(0);
+    let first_hit = hitable.hit(ray, 0.001, f32::MAX);
+    (shade(ray, first_hit.as_ref(), hitable, lights, environment, sampler, None, 0), first_hit)
+}
+
+// scattering_pdf is the probability density with which a diffuse material chose this ray, or
+// None for camera rays and specular bounces, which light sampling could not have found
+fn colour(ray: &Ray3<f32>, hitable: &Hitable, lights: &LightList, environment: &Environment, sampler: &mut Sampler, scattering_pdf: Option<f32>, depth: u32) -> Vector3<f32> {
+    stats::count_ray(depth);
+    shade(ray, hitable.hit(ray, 0.001, f32::MAX).as_ref(), hitable, lights, environment, sampler, scattering_pdf, depth)
+}
+
+// the light leaving the hit, if the ray hit anything, back along the ray
+fn shade(ray: &Ray3<f32>, hit: Option<&HitRecord>, hitable: &Hitable, lights: &LightList, environment: &Environment, sampler: &mut Sampler, scattering_pdf: Option<f32>, depth: u32) -> Vector3<f32> {
+        hit.and_then(|rec| {
+            if rec.material.is_some() {
+                return rec.material.clone().map(|material| {
+                    let mut emitted = material::emitted(&material, &ray, rec);
+                    if let Some(pdf) = scattering_pdf {
+                        if emitted != Vector3::zero() {
+                            emitted *= mis_weight(pdf, lights.pdf(ray.origin, ray.direction));
+                        }
+                    }
+                    if depth < 50 {
+                        let (attenuation, scattered, should_scatter) = material::scatter(&material, &ray, rec, sampler);
+                        if !should_scatter {
+                            stats::count_path_end(PathEnd::Absorbed, depth);
+                        } else {
+                            return match material::evaluate(&material, rec, scattered.direction) {
+                                Some((_, pdf)) => {
+                                    emitted
+                                        + direct_light(rec, &material, ray, hitable, lights, environment, sampler)
+                                        + attenuation.mul_element_wise(colour(&scattered, hitable, lights, environment, sampler, Some(pdf), depth + 1))
+                                }
+                                None => emitted + attenuation.mul_element_wise(colour(&scattered, hitable, lights, environment, sampler, None, depth + 1)),
+                            };
+                        }
+                    } else {
+                        stats::count_path_end(PathEnd::DepthCapped, depth);
+                    }
+                    emitted
+                })
+            } else {
+                None
+            }
+        }).unwrap_or_else(|| {
+            stats::count_path_end(PathEnd::Escaped, depth);
+            let value = environment.value(ray.direction);
+            match scattering_pdf {
+                Some(pdf) => value * mis_weight(pdf, lights.pdf(ray.origin, ray.direction)),
+                None => value,
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sphere::Sphere;
+    use material::Material;
+    use filter::FilterKind;
+    use stats::Primitive;
+
+    use cgmath::Point3;
+
+    fn renderer(settings: RenderSettings) -> Renderer {
+        let world = HitableList::new().with_hitable(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, Material::Lambertian { albedo: Vector3::new(0.8, 0.3, 0.3) }));
+        let camera = Camera::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0), Vector3::unit_y(), 90.0, 1.5, 0.0, 1.0, 0.0, 0.0);
+        Renderer::new(world, camera, settings)
+    }
+
+    #[test]
+    fn renders_into_memory() {
+        let image = renderer(RenderSettings::new(12, 8).with_samples_per_pixel(4)).render();
+        assert_eq!(image.dimensions(), (12, 8));
+        // the sky above and the sphere in the middle
+        assert!(image.get_pixel(0, 0).data[2] > 0.5);
+        let centre = image.get_pixel(6, 4).data;
+        assert!(centre[0] > centre[2]);
+    }
+
+    #[test]
+    fn renders_the_same_image_on_any_number_of_threads() {
+        let settings = RenderSettings::new(12, 8).with_samples_per_pixel(8).with_seed(3).with_tile_size(3).with_filter(Filter { kind: FilterKind::Mitchell, radius: 2.0 });
+        let one = renderer(settings.clone().with_threads(1)).render();
+        let three = renderer(settings.with_threads(3)).render();
+        assert_eq!(one.into_raw(), three.into_raw());
+    }
+
+    #[test]
+    fn upscaled_renders_reach_every_pixel() {
+        for &kind in [FilterKind::Box, FilterKind::Tent, FilterKind::Mitchell].iter() {
+            let filter = Filter { kind, radius: kind.default_radius() };
+            // stratified samples fall in each two by two block of the image's pixels
+            let renderer = renderer(RenderSettings::new(12, 8).with_sampling_resolution(3, 2).with_samples_per_pixel(4).with_sampler(SamplerKind::Stratified).with_filter(filter));
+            let render = renderer.render_from(renderer.start(), |_| Ok::<(), ()>(())).unwrap();
+            assert!(render.checkpoint.film.weights.iter().all(|&weight| weight != 0.0), "{:?}", kind);
+        }
+    }
+
+    #[test]
+    fn reports_each_pass() {
+        let renderer = renderer(RenderSettings::new(6, 4).with_samples_per_pixel(10).with_pass_samples(4));
+        let mut passes = Vec::new();
+        let render = renderer.render_from(renderer.start(), |render| {
+            passes.push((render.checkpoint.accumulation.mean_samples(), render.finished));
+            Ok::<(), ()>(())
+        }).unwrap();
+        assert_eq!(passes, vec![(4.0, false), (8.0, false), (10.0, true)]);
+        assert!(render.finished);
+    }
+
+    #[test]
+    fn counts_the_work_done() {
+        // render passes come from the same trace, so they add no intersection tests of their own
+        let renderer = renderer(RenderSettings::new(6, 4).with_samples_per_pixel(5).with_threads(2).with_aovs(&[Aov::Depth]).with_intersection_stats(true));
+        let render = renderer.render_from(renderer.start(), |_| Ok::<(), ()>(())).unwrap();
+        let stats = render.stats;
+        assert_eq!(stats.primary_rays, 6 * 4 * 5);
+        assert_eq!(stats.paths(), stats.primary_rays);
+        assert_eq!(stats.path_depths.iter().sum::<u64>(), stats.paths());
+        assert_eq!(stats.hit_tests[Primitive::Sphere as usize], stats.primary_rays + stats.secondary_rays + stats.shadow_rays);
+        assert!(stats.secondary_rays > 0);
+    }
+
+    #[test]
+    fn refuses_mismatched_renders() {
+        let settings = RenderSettings::new(6, 4)
+            .with_sampling_resolution(3, 2)
+            .with_seed(1 << 40 | 7)
+            .with_sampler(SamplerKind::Sobol)
+            .with_filter(Filter { kind: FilterKind::Mitchell, radius: 2.0 });
+        let checkpoint = settings.checkpoint("scenes/example.toml");
+        assert!(settings.check_matches("scenes/example.toml", &checkpoint).is_ok());
+        assert!(settings.check_matches("default", &checkpoint).is_err());
+        assert!(settings.clone().with_seed(7).check_matches("scenes/example.toml", &checkpoint).is_err());
+        assert!(settings.clone().with_sampler(SamplerKind::Halton).check_matches("scenes/example.toml", &checkpoint).is_err());
+        assert!(settings.clone().with_filter(Filter { kind: FilterKind::Gaussian, radius: 2.0 }).check_matches("scenes/example.toml", &checkpoint).is_err());
+        assert!(settings.clone().with_filter(Filter { kind: FilterKind::Mitchell, radius: 1.0 }).check_matches("scenes/example.toml", &checkpoint).is_err());
+        assert!(settings.clone().with_sampling_resolution(6, 4).check_matches("scenes/example.toml", &checkpoint).is_err());
+        assert!(RenderSettings::new(3, 2).with_seed(1 << 40 | 7).with_sampler(SamplerKind::Sobol).with_filter(Filter { kind: FilterKind::Mitchell, radius: 2.0 })
+            .check_matches("scenes/example.toml", &checkpoint).is_err());
+    }
+
+    #[test]
+    fn stops_at_the_first_failed_pass() {
+        let renderer = renderer(RenderSettings::new(6, 4).with_samples_per_pixel(10).with_pass_samples(4));
+        let mut passes = 0;
+        let result = renderer.render_from(renderer.start(), |_| {
+            passes += 1;
+            Err("disk full")
+        });
+        assert_eq!(result.err(), Some("disk full"));
+        assert_eq!(passes, 1);
+    }
+}
@@ -0,0 +1,415 @@
+use rand::{
+    Rng,
+    SeedableRng,
+    XorShiftRng,
+};
+
+// the largest f32 below one, so that scaled samples never reach the end of their range
+const ONE_MINUS_EPSILON: f32 = 0.99999994;
+
+const PRIMES: [u32; 64] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53,
+    59, 61, 67, 71, 73, 79, 83, 89, 97, 101, 103, 107, 109, 113, 127, 131,
+    137, 139, 149, 151, 157, 163, 167, 173, 179, 181, 191, 193, 197, 199, 211, 223,
+    227, 229, 233, 239, 241, 251, 257, 263, 269, 271, 277, 281, 283, 293, 307, 311,
+];
+
+/// Supplies the numbers in [0, 1) which drive every random decision made for a sample: the
+/// position within the pixel, the lens, the shutter time, each bounce and each light sample.
+/// Each call takes the next dimension of the current sample, so samplers can spread the samples
+/// of a pixel evenly over every dimension rather than letting them clump.
+pub trait Sampler {
+    /// Starts the given sample of the given pixel from its first dimension.
+    fn start_sample(&mut self, pixel: u64, sample: u32);
+    fn next_1d(&mut self) -> f32;
+    fn next_2d(&mut self) -> (f32, f32);
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum SamplerKind {
+    Random,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "random" => Some(SamplerKind::Random),
+            "stratified" => Some(SamplerKind::Stratified),
+            "halton" => Some(SamplerKind::Halton),
+            "sobol" => Some(SamplerKind::Sobol),
+            _ => None,
+        }
+    }
+
+    /// A sampler for one render thread. `samples_per_pixel` is the most samples any pixel will
+    /// take, which stratified sampling divides each dimension between.
+    pub fn build(&self, seed: u64, samples_per_pixel: u32) -> Box<Sampler> {
+        match *self {
+            SamplerKind::Random => Box::new(RandomSampler::new(seed)),
+            SamplerKind::Stratified => Box::new(StratifiedSampler::new(seed, samples_per_pixel)),
+            SamplerKind::Halton => Box::new(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => Box::new(SobolSampler::new(seed)),
+        }
+    }
+}
+
+// splitmix64's finaliser, which spreads neighbouring inputs across the whole range
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+fn hash(seed: u64, pixel: u64, value: u64) -> u64 {
+    mix(mix(mix(seed) ^ pixel) ^ value)
+}
+
+fn to_unit(bits: u32) -> f32 {
+    ((bits >> 8) as f32 / (1 << 24) as f32).min(ONE_MINUS_EPSILON)
+}
+
+// Random Sampler
+
+/// Independent uniform random numbers, drawn from a stream derived from the seed, pixel and
+/// sample so that the same seed renders the same image however pixels are shared between threads.
+pub struct RandomSampler {
+    seed: u64,
+    rng: XorShiftRng,
+}
+
+impl RandomSampler {
+    pub fn new(seed: u64) -> Self {
+        RandomSampler {
+            seed,
+            rng: XorShiftRng::new_unseeded(),
+        }
+    }
+}
+
+impl Sampler for RandomSampler {
+    fn start_sample(&mut self, pixel: u64, sample: u32) {
+        let key = hash(self.seed, pixel, sample as u64);
+        let more = mix(key);
+        // xorshift cannot start from all zeros
+        self.rng.reseed([key as u32, (key >> 32) as u32, more as u32, (more >> 32) as u32 | 1]);
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        self.rng.gen_range(0.0, 1.0)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        (self.next_1d(), self.next_1d())
+    }
+}
+
+// Stratified Sampler
+
+// Kensler's hashed permutation of 0..length, which finds where index lands without building
+// the whole permutation
+fn permute(index: u32, length: u32, key: u32) -> u32 {
+    let mut w = length - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    let mut i = index;
+    loop {
+        i ^= key;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= key >> 16;
+        i ^= (i & w) >> 4;
+        i ^= key >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= key >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | key >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < length {
+            break;
+        }
+    }
+    (i.wrapping_add(key)) % length
+}
+
+/// Jittered samples, one in each of `samples_per_pixel` strata of every dimension (or of a
+/// square grid for pairs of dimensions), with the strata shuffled independently per dimension.
+pub struct StratifiedSampler {
+    seed: u64,
+    strata: u32,
+    side: u32,
+    pixel: u64,
+    sample: u32,
+    dimension: u64,
+    jitter: RandomSampler,
+}
+
+impl StratifiedSampler {
+    pub fn new(seed: u64, samples_per_pixel: u32) -> Self {
+        let strata = samples_per_pixel.max(1);
+        StratifiedSampler {
+            seed,
+            strata,
+            side: (strata as f32).sqrt() as u32,
+            pixel: 0,
+            sample: 0,
+            dimension: 0,
+            jitter: RandomSampler::new(seed),
+        }
+    }
+
+    fn next_key(&mut self) -> u32 {
+        self.dimension += 1;
+        hash(self.seed, self.pixel, self.dimension) as u32
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_sample(&mut self, pixel: u64, sample: u32) {
+        self.pixel = pixel;
+        self.sample = sample;
+        self.dimension = 0;
+        self.jitter.start_sample(pixel, sample);
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let key = self.next_key();
+        let stratum = permute(self.sample % self.strata, self.strata, key);
+        ((stratum as f32 + self.jitter.next_1d()) / self.strata as f32).min(ONE_MINUS_EPSILON)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let key = self.next_key();
+        let cells = self.side * self.side;
+        let cell = permute(self.sample % cells, cells, key);
+        let (jitter_x, jitter_y) = self.jitter.next_2d();
+        (
+            (((cell % self.side) as f32 + jitter_x) / self.side as f32).min(ONE_MINUS_EPSILON),
+            (((cell / self.side) as f32 + jitter_y) / self.side as f32).min(ONE_MINUS_EPSILON),
+        )
+    }
+}
+
+// Halton Sampler
+
+fn radical_inverse(base: u32, index: u32) -> f32 {
+    let inverse_base = 1f64 / base as f64;
+    let mut index = index;
+    let mut reversed = 0f64;
+    let mut scale = inverse_base;
+    while index > 0 {
+        reversed += (index % base) as f64 * scale;
+        index /= base;
+        scale *= inverse_base;
+    }
+    reversed as f32
+}
+
+/// The Halton sequence, with a prime base per dimension, randomly offset per pixel so that
+/// neighbouring pixels do not share the same pattern. Dimensions beyond the table of primes
+/// fall back to random numbers.
+pub struct HaltonSampler {
+    seed: u64,
+    pixel: u64,
+    sample: u32,
+    dimension: usize,
+    fallback: RandomSampler,
+}
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        HaltonSampler {
+            seed,
+            pixel: 0,
+            sample: 0,
+            dimension: 0,
+            fallback: RandomSampler::new(seed),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_sample(&mut self, pixel: u64, sample: u32) {
+        self.pixel = pixel;
+        self.sample = sample;
+        self.dimension = 0;
+        self.fallback.start_sample(pixel, sample);
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        let dimension = self.dimension;
+        self.dimension += 1;
+        if dimension >= PRIMES.len() {
+            return self.fallback.next_1d();
+        }
+        let offset = to_unit(hash(self.seed, self.pixel, dimension as u64) as u32);
+        let value = radical_inverse(PRIMES[dimension], self.sample) + offset;
+        (value - value.floor()).min(ONE_MINUS_EPSILON)
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        (self.next_1d(), self.next_1d())
+    }
+}
+
+// Sobol Sampler
+
+// the first two dimensions of the Sobol sequence, with the first bit after the binary point
+// in the highest bit
+fn sobol(index: u32, dimension: usize) -> u32 {
+    if dimension == 0 {
+        return index.reverse_bits();
+    }
+    let mut result = 0u32;
+    let mut direction = 1u32 << 31;
+    let mut index = index;
+    while index > 0 {
+        if index & 1 == 1 {
+            result ^= direction;
+        }
+        direction ^= direction >> 1;
+        index >>= 1;
+    }
+    result
+}
+
+fn laine_karras_permutation(x: u32, seed: u32) -> u32 {
+    let mut x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+// scrambles the digits of x so that each one depends on those before it, which keeps the
+// sequence stratified
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+/// Owen scrambled Sobol points, following Burley's "Practical Hash-based Owen Scrambling". Every
+/// pair of dimensions takes the first two Sobol dimensions, with the order of the samples
+/// shuffled differently for each pair so that the pairs are not correlated with one another.
+pub struct SobolSampler {
+    seed: u64,
+    pixel: u64,
+    sample: u32,
+    dimension: u64,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        SobolSampler {
+            seed,
+            pixel: 0,
+            sample: 0,
+            dimension: 0,
+        }
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_sample(&mut self, pixel: u64, sample: u32) {
+        self.pixel = pixel;
+        self.sample = sample;
+        self.dimension = 0;
+    }
+
+    fn next_1d(&mut self) -> f32 {
+        self.next_2d().0
+    }
+
+    fn next_2d(&mut self) -> (f32, f32) {
+        let key = hash(self.seed, self.pixel, self.dimension);
+        self.dimension += 1;
+        let index = nested_uniform_scramble(self.sample, key as u32);
+        let x = nested_uniform_scramble(sobol(index, 0), (key >> 32) as u32);
+        let y = nested_uniform_scramble(sobol(index, 1), mix(key) as u32);
+        (to_unit(x), to_unit(y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(sampler: &mut Sampler, pixel: u64, sample: u32) -> Vec<f32> {
+        sampler.start_sample(pixel, sample);
+        (0..8).map(|_| sampler.next_1d()).collect()
+    }
+
+    #[test]
+    fn samples_repeat_for_the_same_seed_pixel_and_sample() {
+        for &kind in [SamplerKind::Random, SamplerKind::Stratified, SamplerKind::Halton, SamplerKind::Sobol].iter() {
+            let mut sampler = kind.build(7, 64);
+            let first = stream(&mut *sampler, 1234, 5);
+            stream(&mut *sampler, 99, 0);
+            assert_eq!(stream(&mut *sampler, 1234, 5), first);
+            assert_eq!(stream(&mut *kind.build(7, 64), 1234, 5), first);
+            assert!(first.iter().all(|&value| value >= 0.0 && value < 1.0));
+        }
+    }
+
+    #[test]
+    fn samples_differ_between_seeds_pixels_and_samples() {
+        let mut sampler = RandomSampler::new(7);
+        let first = stream(&mut sampler, 1234, 5);
+        assert!(stream(&mut RandomSampler::new(8), 1234, 5) != first);
+        assert!(stream(&mut sampler, 1235, 5) != first);
+        assert!(stream(&mut sampler, 1234, 6) != first);
+        assert!(stream(&mut RandomSampler::new(0), 0, 0) != stream(&mut RandomSampler::new(0), 0, 1));
+    }
+
+    #[test]
+    fn permutations_cover_every_index_once() {
+        for &length in [1, 2, 7, 16, 100].iter() {
+            let mut permuted: Vec<u32> = (0..length).map(|index| permute(index, length, 0xdeadbeef)).collect();
+            permuted.sort();
+            assert_eq!(permuted, (0..length).collect::<Vec<_>>());
+        }
+    }
+
+    // each sampler should put exactly one of n samples in each of n equal intervals of a
+    // dimension, or one in each cell of a grid for pairs of dimensions
+    fn assert_stratified(kind: SamplerKind, samples: u32, side: u32) {
+        let mut sampler = kind.build(3, samples);
+        let mut intervals = vec![0; samples as usize];
+        let mut cells = vec![0; (side * side) as usize];
+        for sample in 0..samples {
+            sampler.start_sample(42, sample);
+            let (x, y) = sampler.next_2d();
+            cells[((y * side as f32) as u32 * side + (x * side as f32) as u32) as usize] += 1;
+            intervals[(sampler.next_1d() * samples as f32) as usize] += 1;
+        }
+        assert!(intervals.iter().all(|&count| count == 1), "{:?} {:?}", kind, intervals);
+        assert!(cells.iter().all(|&count| count == samples / (side * side)), "{:?} {:?}", kind, cells);
+    }
+
+    #[test]
+    fn low_discrepancy_samplers_are_stratified() {
+        assert_stratified(SamplerKind::Stratified, 16, 4);
+        assert_stratified(SamplerKind::Sobol, 16, 4);
+        assert_stratified(SamplerKind::Sobol, 64, 8);
+    }
+
+    #[test]
+    fn halton_matches_the_radical_inverse() {
+        assert_eq!(radical_inverse(2, 1), 0.5);
+        assert_eq!(radical_inverse(2, 6), 0.375);
+        assert!((radical_inverse(3, 5) - 7.0 / 9.0).abs() < 1e-6);
+    }
+}
@@ -1,19 +1,16 @@
-use camera::Camera;
-use sphere::{
-    Sphere,
-    MovingSphere,
+use scenefile::{
+    SceneDescription,
+    EnvironmentDescription,
+    CameraDescription,
+    HitableDescription,
+    MaterialDescription,
+    TextureDescription,
+    RotationDescription,
 };
-use quad::Quad;
-use pyramid::Pyramid;
-use teapot::Teapot;
-use hitable::HitableList;
-use material::Material;
-use plane::Plane;
 use randomutil::seedable_unit_distribution;
-use texture::{
-    ConstantTexture,
-    ChequeredTexture,
-    NoiseTexture,
+use error::{
+    Error,
+    Result,
 };
 
 use cgmath::{
@@ -21,44 +18,40 @@ use cgmath::{
     Point3,
     InnerSpace,
     EuclideanSpace,
-    Quaternion,
-    One,
-    Deg,
-    Rotation3,
 };
 
-use std::f32;
-use std::time::{
-    Instant,
-    Duration,
-};
-
-pub fn random_scene(aspect: f32, motion_blur: bool) -> (HitableList, Camera) {
-    let aperture_open_time = Instant::now();
-    let aperture_duration = Duration::from_millis(1000);
-
-    let ground_level = 0f32;
+use std::collections::BTreeMap;
+
+/// Describes one of the built in scenes by the name given to `--scene`.
+pub fn load(name: &str) -> Result<SceneDescription> {
+    Ok(match name {
+        "random" => random_scene(false),
+        "motionblur" => random_scene(true),
+        "default" => default_scene(),
+        "teapot" => teapot_scene(),
+        "test" => test_scene(),
+        "2spheres" => two_spheres(),
+        "2perlinspheres" => two_perlin_spheres(),
+        "cornell" => cornell_box(),
+        _ => return Err(Error::UnknownScene(name.to_string())),
+    })
+}
 
-    let mut world = HitableList::new()
-        .with_hitable(Quad::new(
-            [
-                Point3::new(-30.0, ground_level, -30.0),
-                Point3::new(30.0, ground_level, -30.0),
-                Point3::new(30.0, ground_level, 30.0),
-                Point3::new(-30.0, ground_level, 30.0),
-            ],
-            Quaternion::from_angle_y(Deg(10f32)),
-            Material::TexturedLambertian {
-                texture: ChequeredTexture::new(
-                    ConstantTexture::new(Vector3::new(0.2, 0.3, 0.1)),
-                    ConstantTexture::new(Vector3::new(0.9, 0.9, 0.9)),
-                )
-            },
-        ))
-        .with_hitable(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, Material::Dielectric { ref_idx: 1.5 }))
-        .with_hitable(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, Material::TexturedLambertian { texture: ConstantTexture::new(Vector3::new(0.4, 0.2, 0.1)) }))
-        .with_hitable(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, Material::Metal { albedo: Vector3::new(0.7, 0.6, 0.5), fuzz: 0.0 }));
+fn camera(from: Point3<f32>, at: Vector3<f32>, aperture: f32, focus_distance: f32) -> CameraDescription {
+    CameraDescription {
+        look_from: from.into(),
+        look_at: at.into(),
+        up: Vector3::unit_y().into(),
+        vfov: 15.0,
+        aperture,
+        focus_distance: Some(focus_distance),
+        shutter_open: 0.0,
+        shutter_duration: 1.0,
+        keyframes: Vec::new(),
+    }
+}
 
+fn random_small_spheres(objects: &mut Vec<HitableDescription>, ground_level: f32, motion_blur: bool) {
     let num = 11;
 
     for a_offset in 0..2 * num {
@@ -66,145 +59,170 @@ pub fn random_scene(aspect: f32, motion_blur: bool) -> (HitableList, Camera) {
         for b_offset in 0..2 * num {
             let b = (b_offset - num) as f32;
             let choose_mat = seedable_unit_distribution();
-            let center = Point3::new(a + 0.9 * seedable_unit_distribution(), 0.2, b + 0.9 * seedable_unit_distribution());
-            if (center.to_vec() - Vector3::new(4.0, 0.2, 0.0)).magnitude() > 0.9 {
-                if choose_mat < 0.8 {
-                    if motion_blur {
-                        world.add_hitable(MovingSphere::new(center, center + Point3::new(0f32, 0.5 * seedable_unit_distribution(), 0f32).to_vec(), aperture_open_time, aperture_duration, 0.2, Material::Lambertian { albedo: Vector3::new(seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution()) }));
-                    } else {
-                        world.add_hitable(Sphere::new(center, 0.2, Material::Lambertian { albedo: Vector3::new(seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution()) }));
-                    }
+            let center = Point3::new(a + 0.9 * seedable_unit_distribution(), 0.2 + ground_level, b + 0.9 * seedable_unit_distribution());
+            if (center.to_vec() - Vector3::new(4.0, 0.2 + ground_level, 0.0)).magnitude() > 0.9 {
+                let center1 = if motion_blur {
+                    Some(center + Point3::new(0f32, 0.5 * seedable_unit_distribution(), 0f32).to_vec())
+                } else {
+                    None
+                };
+                let material = if choose_mat < 0.8 {
+                    MaterialDescription::Lambertian { albedo: [seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution()] }
                 } else if choose_mat < 0.95 {
-                    if motion_blur {
-                        world.add_hitable(MovingSphere::new(center, center + Point3::new(0f32, 0.5 * seedable_unit_distribution(), 0f32).to_vec(), aperture_open_time, aperture_duration, 0.2, Material::Metal { albedo: Vector3::new(0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution())), fuzz: 0.5 * seedable_unit_distribution() }));
-                    } else {
-                        world.add_hitable(Sphere::new(center, 0.2, Material::Metal { albedo: Vector3::new(0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution())), fuzz: 0.5 * seedable_unit_distribution() }));
-                    }
+                    MaterialDescription::Metal { albedo: [0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution())], fuzz: 0.5 * seedable_unit_distribution() }
                 } else {
-                    if motion_blur {
-                        world.add_hitable(MovingSphere::new(center, center + Point3::new(0f32, 0.5 * seedable_unit_distribution(), 0f32).to_vec(), aperture_open_time, aperture_duration, 0.2, Material::Dielectric { ref_idx: 1.5 }));
-                    } else {
-                        world.add_hitable(Sphere::new(center, 0.2, Material::Dielectric { ref_idx: 1.5 }));
-                    }
-                }
+                    MaterialDescription::Dielectric { ref_idx: 1.5 }
+                };
+                objects.push(match center1 {
+                    Some(center1) => HitableDescription::MovingSphere { center0: center.into(), center1: center1.into(), movement_start: 0.0, movement_duration: 1.0, radius: 0.2, material },
+                    None => HitableDescription::Sphere { center: center.into(), radius: 0.2, material },
+                });
             }
         }
     }
+}
+
+pub fn random_scene(motion_blur: bool) -> SceneDescription {
+    let ground_level = 0f32;
+
+    let mut objects = vec![
+        HitableDescription::Quad {
+            vertices: [
+                [-30.0, ground_level, -30.0],
+                [30.0, ground_level, -30.0],
+                [30.0, ground_level, 30.0],
+                [-30.0, ground_level, 30.0],
+            ],
+            rotation: RotationDescription::about_y(10f32),
+            material: MaterialDescription::TexturedLambertian {
+                texture: TextureDescription::Chequered {
+                    odd: Box::new(TextureDescription::Constant { colour: [0.2, 0.3, 0.1] }),
+                    even: Box::new(TextureDescription::Constant { colour: [0.9, 0.9, 0.9] }),
+                }
+            },
+        },
+        HitableDescription::Sphere { center: [0.0, 1.0, 0.0], radius: 1.0, material: MaterialDescription::Dielectric { ref_idx: 1.5 } },
+        HitableDescription::Sphere { center: [-4.0, 1.0, 0.0], radius: 1.0, material: MaterialDescription::TexturedLambertian { texture: TextureDescription::Constant { colour: [0.4, 0.2, 0.1] } } },
+        HitableDescription::Sphere { center: [4.0, 1.0, 0.0], radius: 1.0, material: MaterialDescription::Metal { albedo: [0.7, 0.6, 0.5], fuzz: 0.0 } },
+    ];
+
+    random_small_spheres(&mut objects, ground_level, motion_blur);
 
     let from = Point3::new(24.0, 2.0, 6.0);
     let at = Vector3::unit_y();
     let dist_to_focus = (from.to_vec() - at).magnitude();
     let aperture = 0.001;
-    let camera = Camera::new(from, at, Vector3::unit_y(), 15.0, aspect, aperture, dist_to_focus, aperture_open_time, aperture_duration);
 
-    (world, camera)
+    SceneDescription { environment: EnvironmentDescription::default(), camera: camera(from, at, aperture, dist_to_focus), shapes: BTreeMap::new(), objects }
 }
 
-pub fn default_scene(aspect: f32) -> (HitableList, Camera) {
+pub fn default_scene() -> SceneDescription {
     let ground_level = -0.5;
-    let world = HitableList::new()
-        .with_hitable(Plane::new(Point3::new(0.0, ground_level, 0.0), Vector3::unit_y(), Material::Lambertian { albedo: Vector3::new(0.8, 0.8, 0.0) }))
-        .with_hitable(Sphere::new(Point3::new(0.0, 0.0, -1.0), 0.5, Material::Lambertian { albedo: Vector3::new(0.1, 0.2, 0.5) }))
-        .with_hitable(Sphere::new(Point3::new(1.0, 0.0, -1.0), 0.5, Material::Metal { albedo: Vector3::new(0.8, 0.6, 0.2), fuzz: 0.3 }))
-        .with_hitable(Sphere::new(Point3::new(-1.0, 0.0, -1.0), 0.5, Material::Dielectric { ref_idx: 1.5 }));
+    let objects = vec![
+        HitableDescription::Plane { center: [0.0, ground_level, 0.0], normal: [0.0, 1.0, 0.0], material: MaterialDescription::Lambertian { albedo: [0.8, 0.8, 0.0] } },
+        HitableDescription::Sphere { center: [0.0, 0.0, -1.0], radius: 0.5, material: MaterialDescription::Lambertian { albedo: [0.1, 0.2, 0.5] } },
+        HitableDescription::Sphere { center: [1.0, 0.0, -1.0], radius: 0.5, material: MaterialDescription::Metal { albedo: [0.8, 0.6, 0.2], fuzz: 0.3 } },
+        HitableDescription::Sphere { center: [-1.0, 0.0, -1.0], radius: 0.5, material: MaterialDescription::Dielectric { ref_idx: 1.5 } },
+    ];
 
     let from = Point3::new(6.0, 1.0, 2.0);
     let at = Vector3::new(0.0, 0.0, -1.1);
     let dist_to_focus = (from.to_vec() - at).magnitude();
     let aperture = 0.001;
-    let camera = Camera::new(from, at, Vector3::unit_y(), 15.0, aspect, aperture, dist_to_focus, Instant::now(), Duration::from_millis(1000));
 
-    (world, camera)
+    SceneDescription { environment: EnvironmentDescription::default(), camera: camera(from, at, aperture, dist_to_focus), shapes: BTreeMap::new(), objects }
 }
 
-pub fn teapot_scene(aspect: f32) -> (HitableList, Camera) {
+pub fn teapot_scene() -> SceneDescription {
     let ground_level = 0.0;
     let teapot_scale = 2.5;
 
-    let mut world = HitableList::new()
-        .with_hitable(Quad::new(
-            [
-                Point3::new(-30.0, ground_level, -30.0),
-                Point3::new(30.0, ground_level, -30.0),
-                Point3::new(30.0, ground_level, 30.0),
-                Point3::new(-30.0, ground_level, 30.0),
+    let mut objects = vec![
+        HitableDescription::Quad {
+            vertices: [
+                [-30.0, ground_level, -30.0],
+                [30.0, ground_level, -30.0],
+                [30.0, ground_level, 30.0],
+                [-30.0, ground_level, 30.0],
             ],
-            Quaternion::from_angle_y(Deg(90f32)),
-            Material::Lambertian { albedo: Vector3::new(0.5, 0.5, 0.5) }
-        ))
-        .with_hitable(Teapot::new(Point3::new(0.0, ground_level + teapot_scale / 2.0, 4.0), teapot_scale, Quaternion::one(), Material::Lambertian { albedo: Vector3::new(0.1, 0.2, 0.5) }))
-        .with_hitable(Teapot::new(Point3::new(0.0, ground_level + teapot_scale / 2.0, -4.0), teapot_scale, Quaternion::one(), Material::Metal { albedo: Vector3::new(0.7, 0.6, 0.5), fuzz: 0.0 }))
-        .with_hitable(Teapot::new(Point3::new(0.0, ground_level + teapot_scale / 2.0, 0.0), teapot_scale, Quaternion::one(), Material::Dielectric { ref_idx: 1.5 }));
-
-    let num = 11;
-
-    for a_offset in 0..2 * num {
-        let a = (a_offset - num) as f32;
-        for b_offset in 0..2 * num {
-            let b = (b_offset - num) as f32;
-            let choose_mat = seedable_unit_distribution();
-            let center = Point3::new(a + 0.9 * seedable_unit_distribution(), 0.2 + ground_level, b + 0.9 * seedable_unit_distribution());
-            if (center.to_vec() - Vector3::new(4.0, 0.2 + ground_level, 0.0)).magnitude() > 0.9 {
-                if choose_mat < 0.8 {
-                    world.add_hitable(Sphere::new(center, 0.2, Material::Lambertian { albedo: Vector3::new(seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution()) }));
-                } else if choose_mat < 0.95 {
-                    world.add_hitable(Sphere::new(center, 0.2, Material::Metal { albedo: Vector3::new(0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution())), fuzz: 0.5 * seedable_unit_distribution() }));
-                } else {
-                    world.add_hitable(Sphere::new(center, 0.2, Material::Dielectric { ref_idx: 1.5 }));
-                }
-            }
-        }
+            rotation: RotationDescription::about_y(90f32),
+            material: MaterialDescription::Lambertian { albedo: [0.5, 0.5, 0.5] },
+        },
+    ];
+    let teapots = [
+        (4.0, MaterialDescription::Lambertian { albedo: [0.1, 0.2, 0.5] }),
+        (-4.0, MaterialDescription::Metal { albedo: [0.7, 0.6, 0.5], fuzz: 0.0 }),
+        (0.0, MaterialDescription::Dielectric { ref_idx: 1.5 }),
+    ];
+    for &(z, ref material) in teapots.iter() {
+        objects.push(HitableDescription::Instance {
+            shape: "teapot".to_string(),
+            translation: [0.0, ground_level + teapot_scale / 2.0, z],
+            scale: [teapot_scale; 3],
+            rotation: RotationDescription::default(),
+            material: Some(material.clone()),
+            keyframes: Vec::new(),
+        });
     }
 
+    random_small_spheres(&mut objects, ground_level, false);
+
     let from = Point3::new(14.0, 1.5, -22.0);
     let at = Vector3::new(0.0, 1.0, -0.5);
     let dist_to_focus = (from.to_vec() - at).magnitude();
     let aperture = 0.001;
-    let camera = Camera::new(from, at, Vector3::unit_y(), 15.0, aspect, aperture, dist_to_focus, Instant::now(), Duration::from_millis(1000));
 
-    (world, camera)
+    let mut shapes = BTreeMap::new();
+    shapes.insert("teapot".to_string(), HitableDescription::Teapot {
+        centre: [0.0, 0.0, 0.0],
+        scale: 1.0,
+        rotation: RotationDescription::default(),
+        material: MaterialDescription::Lambertian { albedo: [0.5, 0.5, 0.5] },
+    });
+
+    SceneDescription { environment: EnvironmentDescription::default(), camera: camera(from, at, aperture, dist_to_focus), shapes, objects }
 }
 
-pub fn two_spheres(aspect: f32) -> (HitableList, Camera) {
-    let texture = Material::TexturedLambertian {
-        texture: ChequeredTexture::new(
-            ConstantTexture::new(Vector3::new(0.2, 0.3, 0.1)),
-            ConstantTexture::new(Vector3::new(0.9, 0.9, 0.9)),
-        )
+pub fn two_spheres() -> SceneDescription {
+    let texture = MaterialDescription::TexturedLambertian {
+        texture: TextureDescription::Chequered {
+            odd: Box::new(TextureDescription::Constant { colour: [0.2, 0.3, 0.1] }),
+            even: Box::new(TextureDescription::Constant { colour: [0.9, 0.9, 0.9] }),
+        }
     };
 
-    let world = HitableList::new()
-        .with_hitable(Sphere::new(Point3::new(0.0, -10.0, 0.0), 10f32, texture.clone()))
-        .with_hitable(Sphere::new(Point3::new(0.0, 10.0, 0.0), 10f32, texture.clone()));
+    let objects = vec![
+        HitableDescription::Sphere { center: [0.0, -10.0, 0.0], radius: 10f32, material: texture.clone() },
+        HitableDescription::Sphere { center: [0.0, 10.0, 0.0], radius: 10f32, material: texture.clone() },
+    ];
 
     let from = Point3::new(13.0, 2.0, 3.0);
     let at = Vector3::new(0.0, 0.0, 0.0);
     let dist_to_focus = 10.0;
     let aperture = 0.0;
-    let camera = Camera::new(from, at, Vector3::unit_y(), 15.0, aspect, aperture, dist_to_focus, Instant::now(), Duration::from_millis(1000));
 
-    (world, camera)
+    SceneDescription { environment: EnvironmentDescription::default(), camera: camera(from, at, aperture, dist_to_focus), shapes: BTreeMap::new(), objects }
 }
 
-pub fn two_perlin_spheres(aspect: f32) -> (HitableList, Camera) {
-    let material = Material::TexturedLambertian {
-        texture: NoiseTexture::new(0.01),
+pub fn two_perlin_spheres() -> SceneDescription {
+    let material = MaterialDescription::TexturedLambertian {
+        texture: TextureDescription::Noise { scale: 0.01 },
     };
 
-    let world = HitableList::new()
-        .with_hitable(Sphere::new(Point3::new(0.0, -1000.0, 0.0), 1000f32, material.clone()))
-        .with_hitable(Sphere::new(Point3::new(0.0, 2.0, 0.0), 2f32, material.clone()));
+    let objects = vec![
+        HitableDescription::Sphere { center: [0.0, -1000.0, 0.0], radius: 1000f32, material: material.clone() },
+        HitableDescription::Sphere { center: [0.0, 2.0, 0.0], radius: 2f32, material: material.clone() },
+    ];
 
     let from = Point3::new(13.0, 2.0, 3.0);
     let at = Vector3::new(0.0, 0.0, 0.0);
     let dist_to_focus = 10.0;
     let aperture = 0.0;
-    let camera = Camera::new(from, at, Vector3::unit_y(), 15.0, aspect, aperture, dist_to_focus, Instant::now(), Duration::from_millis(1000));
 
-    (world, camera)
+    SceneDescription { environment: EnvironmentDescription::default(), camera: camera(from, at, aperture, dist_to_focus), shapes: BTreeMap::new(), objects }
 }
 
-pub fn test_scene(aspect: f32) -> (HitableList, Camera) {
+pub fn test_scene() -> SceneDescription {
     let ground_level = -0.5;
     let pyramid_base = 250f32;
     let pyramid_height = 100f32;
@@ -212,46 +230,70 @@ pub fn test_scene(aspect: f32) -> (HitableList, Camera) {
     let glass_teapot_scale = 1.2;
     let sphere_radius = 1.0;
 
-    let mut world = HitableList::new()
-        .with_hitable(Pyramid::new(
-            Point3::new(-400.0, ground_level, -1200.0),
-            pyramid_base,
-            pyramid_height,
-            Quaternion::from_angle_y(Deg(45f32)),
-            Material::Lambertian { albedo: Vector3::new(0.4, 0.2, 0.1) }
-        ))
-        .with_hitable(Plane::new(Point3::new(0.0, ground_level, 0.0), Vector3::unit_y(), Material::Lambertian { albedo: Vector3::new(0.8, 0.5, 0.2) }))
-        .with_hitable(Teapot::new(Point3::new(-25.0, ground_level + shiny_teapot_scale / 2.0, -40.0), shiny_teapot_scale, Quaternion::one(), Material::Metal { albedo: Vector3::new(0.7, 0.6, 0.5), fuzz: 0.0 }))
-        .with_hitable(Teapot::new(Point3::new(8.5, ground_level + glass_teapot_scale / 2.0, 15.0), glass_teapot_scale, Quaternion::from_angle_y(Deg(240f32)), Material::Dielectric { ref_idx: 1.5 }))
-        .with_hitable(Sphere::new(Point3::new(-10.0, sphere_radius + ground_level, -25.0), sphere_radius, Material::Dielectric { ref_idx: 1.5 }))
-        .with_hitable(Sphere::new(Point3::new(-4.0, sphere_radius + ground_level, -20.0), sphere_radius, Material::Lambertian { albedo: Vector3::new(0.1, 0.2, 0.5) }))
-        .with_hitable(Sphere::new(Point3::new(-21.0, sphere_radius + ground_level, -60.0), sphere_radius, Material::Metal { albedo: Vector3::new(0.7, 0.6, 0.5), fuzz: 0.0 }));
-
-    let num = 11;
-
-    for a_offset in 0..2 * num {
-        let a = (a_offset - num) as f32;
-        for b_offset in 0..2 * num {
-            let b = (b_offset - num) as f32;
-            let choose_mat = seedable_unit_distribution();
-            let center = Point3::new(a + 0.9 * seedable_unit_distribution(), 0.2 + ground_level, b + 0.9 * seedable_unit_distribution());
-            if (center.to_vec() - Vector3::new(4.0, 0.2 + ground_level, 0.0)).magnitude() > 0.9 {
-                if choose_mat < 0.8 {
-                    world.add_hitable(Sphere::new(center, 0.2, Material::Lambertian { albedo: Vector3::new(seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution(), seedable_unit_distribution() * seedable_unit_distribution()) }));
-                } else if choose_mat < 0.95 {
-                    world.add_hitable(Sphere::new(center, 0.2, Material::Metal { albedo: Vector3::new(0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution()), 0.5 * (1.0 + seedable_unit_distribution())), fuzz: 0.5 * seedable_unit_distribution() }));
-                } else {
-                    world.add_hitable(Sphere::new(center, 0.2, Material::Dielectric { ref_idx: 1.5 }));
-                }
-            }
-        }
-    }
+    let mut objects = vec![
+        HitableDescription::Pyramid {
+            position: [-400.0, ground_level, -1200.0],
+            base_length: pyramid_base,
+            height: pyramid_height,
+            rotation: RotationDescription::about_y(45f32),
+            material: MaterialDescription::Lambertian { albedo: [0.4, 0.2, 0.1] },
+        },
+        HitableDescription::Plane { center: [0.0, ground_level, 0.0], normal: [0.0, 1.0, 0.0], material: MaterialDescription::Lambertian { albedo: [0.8, 0.5, 0.2] } },
+        HitableDescription::Teapot { centre: [-25.0, ground_level + shiny_teapot_scale / 2.0, -40.0], scale: shiny_teapot_scale, rotation: RotationDescription::default(), material: MaterialDescription::Metal { albedo: [0.7, 0.6, 0.5], fuzz: 0.0 } },
+        HitableDescription::Teapot { centre: [8.5, ground_level + glass_teapot_scale / 2.0, 15.0], scale: glass_teapot_scale, rotation: RotationDescription::about_y(240f32), material: MaterialDescription::Dielectric { ref_idx: 1.5 } },
+        HitableDescription::Sphere { center: [-10.0, sphere_radius + ground_level, -25.0], radius: sphere_radius, material: MaterialDescription::Dielectric { ref_idx: 1.5 } },
+        HitableDescription::Sphere { center: [-4.0, sphere_radius + ground_level, -20.0], radius: sphere_radius, material: MaterialDescription::Lambertian { albedo: [0.1, 0.2, 0.5] } },
+        HitableDescription::Sphere { center: [-21.0, sphere_radius + ground_level, -60.0], radius: sphere_radius, material: MaterialDescription::Metal { albedo: [0.7, 0.6, 0.5], fuzz: 0.0 } },
+    ];
+
+    random_small_spheres(&mut objects, ground_level, false);
 
     let from = Point3::new(11.4, 1.0, 22.8);
     let at = Vector3::new(0.75, 0.0, 0.5);
     let dist_to_focus = (from.to_vec() - at).magnitude();
     let aperture = 0.001;
-    let camera = Camera::new(from, at, Vector3::unit_y(), 15.0, aspect, aperture, dist_to_focus, Instant::now(), Duration::from_millis(1000));
 
-    (world, camera)
+    SceneDescription { environment: EnvironmentDescription::default(), camera: camera(from, at, aperture, dist_to_focus), shapes: BTreeMap::new(), objects }
+}
+
+pub fn cornell_box() -> SceneDescription {
+    let red = MaterialDescription::Lambertian { albedo: [0.65, 0.05, 0.05] };
+    let white = MaterialDescription::Lambertian { albedo: [0.73, 0.73, 0.73] };
+    let green = MaterialDescription::Lambertian { albedo: [0.12, 0.45, 0.15] };
+    let light = MaterialDescription::DiffuseLight { emit: TextureDescription::Constant { colour: [15.0, 15.0, 15.0] }, one_sided: true };
+
+    // quad vertices wind clockwise when seen from the side their normal faces, which is into the box
+    let quad = |vertices: [[f32; 3]; 4], material: &MaterialDescription| HitableDescription::Quad {
+        vertices,
+        rotation: RotationDescription::default(),
+        material: material.clone(),
+    };
+
+    let objects = vec![
+        quad([[555.0, 0.0, 0.0], [555.0, 555.0, 0.0], [555.0, 555.0, 555.0], [555.0, 0.0, 555.0]], &green),
+        quad([[0.0, 0.0, 0.0], [0.0, 0.0, 555.0], [0.0, 555.0, 555.0], [0.0, 555.0, 0.0]], &red),
+        quad([[0.0, 0.0, 0.0], [555.0, 0.0, 0.0], [555.0, 0.0, 555.0], [0.0, 0.0, 555.0]], &white),
+        quad([[0.0, 555.0, 0.0], [0.0, 555.0, 555.0], [555.0, 555.0, 555.0], [555.0, 555.0, 0.0]], &white),
+        quad([[0.0, 0.0, 555.0], [555.0, 0.0, 555.0], [555.0, 555.0, 555.0], [0.0, 555.0, 555.0]], &white),
+        quad([[213.0, 554.0, 227.0], [213.0, 554.0, 332.0], [343.0, 554.0, 332.0], [343.0, 554.0, 227.0]], &light),
+        HitableDescription::Sphere { center: [190.0, 90.0, 190.0], radius: 90.0, material: MaterialDescription::Dielectric { ref_idx: 1.5 } },
+        HitableDescription::Sphere { center: [370.0, 120.0, 370.0], radius: 120.0, material: MaterialDescription::Metal { albedo: [0.8, 0.85, 0.88], fuzz: 0.05 } },
+    ];
+
+    SceneDescription {
+        environment: EnvironmentDescription::Constant { colour: [0.0, 0.0, 0.0] },
+        camera: CameraDescription {
+            look_from: [278.0, 278.0, -800.0],
+            look_at: [278.0, 278.0, 0.0],
+            up: [0.0, 1.0, 0.0],
+            vfov: 40.0,
+            aperture: 0.0,
+            focus_distance: Some(10.0),
+            shutter_open: 0.0,
+            shutter_duration: 1.0,
+            keyframes: Vec::new(),
+        },
+        shapes: BTreeMap::new(),
+        objects,
+    }
 }
@@ -0,0 +1,896 @@
+use camera::Camera;
+use sphere::{
+    Sphere,
+    MovingSphere,
+};
+use quad::Quad;
+use cuboid::{
+    Cuboid,
+    Face,
+};
+use pyramid::Pyramid;
+use teapot::Teapot;
+use mesh::Mesh;
+use obj::ObjError;
+use triangle::{
+    Triangle,
+    NormalTriangle,
+};
+use plane::Plane;
+use instance::{
+    Instance,
+    MovingInstance,
+};
+use animation::Curve;
+use transform::{
+    Transform,
+    AnimatedTransform,
+    Keyframe,
+};
+use hitable::{
+    Hitable,
+    HitableList,
+    Identified,
+};
+use environment::{
+    Environment,
+    EnvironmentMap,
+    EnvironmentLight,
+};
+use light::{
+    LightList,
+    SphereLight,
+    TriangleLight,
+};
+use material::Material;
+use texture::{
+    Texture,
+    ConstantTexture,
+    ChequeredTexture,
+    NoiseTexture,
+};
+
+use cgmath::{
+    Vector3,
+    Point3,
+    InnerSpace,
+    Quaternion,
+    Deg,
+    Rotation,
+    Rotation3,
+    One,
+};
+use toml;
+
+use std::{
+    f32,
+    fmt,
+    fs,
+    io,
+};
+use std::io::Read;
+use std::path::Path;
+use std::collections::BTreeMap;
+use std::cmp::Ordering;
+use std::sync::Arc;
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct SceneDescription {
+    #[serde(default)]
+    pub environment: EnvironmentDescription,
+    pub camera: CameraDescription,
+    // geometry built once and placed any number of times by instance objects
+    #[serde(default)]
+    pub shapes: BTreeMap<String, HitableDescription>,
+    #[serde(default)]
+    pub objects: Vec<HitableDescription>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CameraDescription {
+    pub look_from: [f32; 3],
+    pub look_at: [f32; 3],
+    #[serde(default = "default_up")]
+    pub up: [f32; 3],
+    pub vfov: f32,
+    #[serde(default)]
+    pub aperture: f32,
+    #[serde(default)]
+    pub focus_distance: Option<f32>,
+    // the scene time in seconds at which the shutter opens
+    #[serde(default)]
+    pub shutter_open: f32,
+    #[serde(default = "default_shutter_duration")]
+    pub shutter_duration: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keyframes: Vec<CameraKeyframeDescription>,
+}
+
+/// Where the camera is at `time` seconds of scene time. Settings left out are the camera's own.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct CameraKeyframeDescription {
+    pub time: f32,
+    pub look_from: [f32; 3],
+    pub look_at: [f32; 3],
+    #[serde(default)]
+    pub vfov: Option<f32>,
+    #[serde(default)]
+    pub aperture: Option<f32>,
+    #[serde(default)]
+    pub focus_distance: Option<f32>,
+    #[serde(default)]
+    pub curve: Curve,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EnvironmentDescription {
+    Constant {
+        colour: [f32; 3],
+    },
+    Gradient {
+        bottom: [f32; 3],
+        top: [f32; 3],
+    },
+    /// An equirectangular Radiance `.hdr` image, turned `rotation` degrees about the vertical axis.
+    Image {
+        path: String,
+        #[serde(default)]
+        rotation: f32,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+}
+
+impl Default for EnvironmentDescription {
+    fn default() -> Self {
+        EnvironmentDescription::Gradient { bottom: [1.0, 1.0, 1.0], top: [0.5, 0.7, 1.0] }
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HitableDescription {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+        material: MaterialDescription,
+    },
+    MovingSphere {
+        center0: [f32; 3],
+        center1: [f32; 3],
+        #[serde(default)]
+        movement_start: f32,
+        #[serde(default = "default_shutter_duration")]
+        movement_duration: f32,
+        radius: f32,
+        material: MaterialDescription,
+    },
+    Plane {
+        center: [f32; 3],
+        normal: [f32; 3],
+        material: MaterialDescription,
+    },
+    Quad {
+        vertices: [[f32; 3]; 4],
+        #[serde(default)]
+        rotation: RotationDescription,
+        material: MaterialDescription,
+    },
+    /// A box between two opposite corners, turned about its centre by `rotation`. Faces given a
+    /// material in `faces` use it in place of `material`.
+    Cuboid {
+        min: [f32; 3],
+        max: [f32; 3],
+        #[serde(default)]
+        rotation: RotationDescription,
+        material: MaterialDescription,
+        #[serde(default, skip_serializing_if = "FacesDescription::is_empty")]
+        faces: FacesDescription,
+    },
+    Pyramid {
+        position: [f32; 3],
+        base_length: f32,
+        height: f32,
+        #[serde(default)]
+        rotation: RotationDescription,
+        material: MaterialDescription,
+    },
+    Teapot {
+        centre: [f32; 3],
+        scale: f32,
+        #[serde(default)]
+        rotation: RotationDescription,
+        material: MaterialDescription,
+    },
+    Triangle {
+        vertices: [[f32; 3]; 3],
+        #[serde(default)]
+        normals: Option<[[f32; 3]; 3]>,
+        material: MaterialDescription,
+    },
+    Mesh {
+        path: String,
+        centre: [f32; 3],
+        #[serde(default = "default_scale")]
+        scale: f32,
+        #[serde(default)]
+        rotation: RotationDescription,
+        #[serde(default)]
+        material: Option<MaterialDescription>,
+    },
+    /// One of the scene's shapes, scaled, then rotated, then translated, and given a material
+    /// of its own if `material` is set. Keyframes, if there are any, move the instance over time
+    /// in place of its translation, scale and rotation.
+    Instance {
+        shape: String,
+        #[serde(default)]
+        translation: [f32; 3],
+        #[serde(default = "default_scale_vector")]
+        scale: [f32; 3],
+        #[serde(default)]
+        rotation: RotationDescription,
+        #[serde(default)]
+        material: Option<MaterialDescription>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        keyframes: Vec<KeyframeDescription>,
+    },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MaterialDescription {
+    Metal {
+        albedo: [f32; 3],
+        fuzz: f32,
+    },
+    Lambertian {
+        albedo: [f32; 3],
+    },
+    TexturedLambertian {
+        texture: TextureDescription,
+    },
+    Dielectric {
+        ref_idx: f32,
+    },
+    DiffuseLight {
+        #[serde(default)]
+        one_sided: bool,
+        emit: TextureDescription,
+    },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TextureDescription {
+    Constant {
+        colour: [f32; 3],
+    },
+    Chequered {
+        odd: Box<TextureDescription>,
+        even: Box<TextureDescription>,
+    },
+    Noise {
+        scale: f32,
+    },
+}
+
+/// A rotation of `angle` degrees about `axis`.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct RotationDescription {
+    pub axis: [f32; 3],
+    pub angle: f32,
+}
+
+impl Default for RotationDescription {
+    fn default() -> Self {
+        RotationDescription { axis: [0.0, 1.0, 0.0], angle: 0.0 }
+    }
+}
+
+/// Materials for some of the faces of a cuboid.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Default)]
+pub struct FacesDescription {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left: Option<MaterialDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right: Option<MaterialDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom: Option<MaterialDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top: Option<MaterialDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub back: Option<MaterialDescription>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub front: Option<MaterialDescription>,
+}
+
+impl FacesDescription {
+    fn is_empty(&self) -> bool {
+        *self == FacesDescription::default()
+    }
+
+    fn build_onto(&self, cuboid: Cuboid) -> Cuboid {
+        let faces = [
+            (Face::Left, &self.left),
+            (Face::Right, &self.right),
+            (Face::Bottom, &self.bottom),
+            (Face::Top, &self.top),
+            (Face::Back, &self.back),
+            (Face::Front, &self.front),
+        ];
+        faces.iter().fold(cuboid, |cuboid, &(face, material)| match *material {
+            Some(ref material) => cuboid.with_face_material(face, material.build()),
+            None => cuboid,
+        })
+    }
+}
+
+/// Where an instance is at `time` seconds of scene time.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
+pub struct KeyframeDescription {
+    pub time: f32,
+    #[serde(default)]
+    pub translation: [f32; 3],
+    #[serde(default = "default_scale_vector")]
+    pub scale: [f32; 3],
+    #[serde(default)]
+    pub curve: Curve,
+    #[serde(default)]
+    pub rotation: RotationDescription,
+}
+
+fn default_up() -> [f32; 3] {
+    [0.0, 1.0, 0.0]
+}
+
+fn default_shutter_duration() -> f32 {
+    1.0
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+fn default_scale_vector() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_intensity() -> f32 {
+    1.0
+}
+
+pub struct Scene {
+    pub world: HitableList,
+    pub lights: LightList,
+    pub environment: Arc<Environment>,
+    pub camera: Camera,
+}
+
+/// A scene built once to be rendered at any number of scene times, such as the frames of an
+/// animation. Its static objects are gathered under a BVH which every frame shares, leaving only
+/// the moving objects to be bounded again for each frame.
+pub struct Animation {
+    static_world: Arc<Hitable + Sync + Send>,
+    moving: Vec<Arc<Hitable + Sync + Send>>,
+    lights: LightList,
+    environment: Arc<Environment>,
+    camera: CameraDescription,
+    aspect: f32,
+}
+
+impl Animation {
+    /// The scene with the shutter opening at `time` and staying open for the camera's shutter
+    /// duration, but no longer than `frame_duration`.
+    pub fn frame(&self, time: f32, frame_duration: f32) -> Scene {
+        let camera = self.camera.at(time).build_shutter(self.aspect, time, time + self.camera.shutter_duration.min(frame_duration));
+        let world = self.moving.iter().fold(HitableList::new().with_shared(self.static_world.clone()), |world, hitable| world.with_shared(hitable.clone()));
+        Scene { world, lights: self.lights.clone(), environment: self.environment.clone(), camera }
+    }
+}
+
+impl SceneDescription {
+    pub fn build(&self, aspect: f32) -> Result<Scene, SceneFileError> {
+        Ok(self.animate(aspect)?.frame(self.camera.shutter_open, f32::INFINITY))
+    }
+
+    pub fn animate(&self, aspect: f32) -> Result<Animation, SceneFileError> {
+        if !(self.camera.shutter_duration >= 0.0) {
+            return Err(SceneFileError::Parse { line: None, column: None, message: "the camera's shutter_duration is negative or not a number".to_string() });
+        }
+        let environment = Arc::new(self.environment.build()?);
+        // shapes cannot be instances themselves, so they are built without any shapes to refer to
+        let mut shapes = BTreeMap::new();
+        for (name, shape) in &self.shapes {
+            shapes.insert(name.clone(), Arc::from(shape.build(&BTreeMap::new())?));
+        }
+        let mut static_world = HitableList::new();
+        let mut moving: Vec<Arc<Hitable + Sync + Send>> = Vec::new();
+        let mut lights = LightList::new();
+        for (index, object) in self.objects.iter().enumerate() {
+            let hitable = Identified::new(index as u32 + 1, object.build(&shapes)?);
+            if object.moves() {
+                moving.push(Arc::new(hitable));
+            } else {
+                static_world = static_world.with_hitable(hitable);
+            }
+            lights = object.add_lights_to(lights);
+        }
+        if let Environment::Image(_) = *environment {
+            lights = lights.with_light(EnvironmentLight::new(environment.clone()));
+        }
+        Ok(Animation {
+            // static objects have the same bounds at any time
+            static_world: Arc::new(static_world.into_bvh(0f32, 0f32)),
+            moving,
+            lights,
+            environment,
+            camera: self.camera.clone(),
+            aspect,
+        })
+    }
+}
+
+impl EnvironmentDescription {
+    pub fn build(&self) -> Result<Environment, SceneFileError> {
+        Ok(match *self {
+            EnvironmentDescription::Constant { colour } => Environment::Constant { colour: Vector3::from(colour) },
+            EnvironmentDescription::Gradient { bottom, top } => Environment::Gradient { bottom: Vector3::from(bottom), top: Vector3::from(top) },
+            EnvironmentDescription::Image { ref path, rotation, intensity } => {
+                let map = EnvironmentMap::load(path, rotation, intensity).map_err(|err| SceneFileError::Environment {
+                    path: path.clone(),
+                    message: err.to_string(),
+                })?;
+                Environment::Image(map)
+            }
+        })
+    }
+}
+
+impl CameraDescription {
+    /// The camera where its keyframes have it when the shutter opens.
+    pub fn build(&self, aspect: f32) -> Camera {
+        self.at(self.shutter_open).build_shutter(aspect, self.shutter_open, self.shutter_open + self.shutter_duration)
+    }
+
+    fn build_shutter(&self, aspect: f32, shutter_open: f32, shutter_close: f32) -> Camera {
+        Camera::new(Point3::from(self.look_from), Vector3::from(self.look_at), Vector3::from(self.up), self.vfov, aspect, self.aperture, self.focus(), shutter_open, shutter_close)
+    }
+
+    // the focus distance, which is the distance to what the camera looks at unless given
+    fn focus(&self) -> f32 {
+        self.focus_distance.unwrap_or_else(|| (Vector3::from(self.look_from) - Vector3::from(self.look_at)).magnitude())
+    }
+
+    /// The camera as its keyframes have it at a scene time, without the keyframes.
+    pub fn at(&self, time: f32) -> CameraDescription {
+        let mut keyframes: Vec<&CameraKeyframeDescription> = self.keyframes.iter().collect();
+        keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+        let placed = |keyframe: &CameraKeyframeDescription| CameraDescription {
+            look_from: keyframe.look_from,
+            look_at: keyframe.look_at,
+            vfov: keyframe.vfov.unwrap_or(self.vfov),
+            aperture: keyframe.aperture.unwrap_or(self.aperture),
+            focus_distance: keyframe.focus_distance.or(self.focus_distance),
+            keyframes: Vec::new(),
+            ..self.clone()
+        };
+        match keyframes.iter().position(|keyframe| keyframe.time > time) {
+            _ if keyframes.is_empty() => CameraDescription { keyframes: Vec::new(), ..self.clone() },
+            Some(0) => placed(keyframes[0]),
+            None => placed(keyframes[keyframes.len() - 1]),
+            Some(next) => {
+                let (from_keyframe, to_keyframe) = (keyframes[next - 1], keyframes[next]);
+                let (from, to) = (placed(from_keyframe), placed(to_keyframe));
+                let amount = from_keyframe.curve.apply((time - from_keyframe.time) / (to_keyframe.time - from_keyframe.time));
+                let lerp = |a: f32, b: f32| a + (b - a) * amount;
+                let lerp_point = |a: [f32; 3], b: [f32; 3]| [lerp(a[0], b[0]), lerp(a[1], b[1]), lerp(a[2], b[2])];
+                CameraDescription {
+                    look_from: lerp_point(from.look_from, to.look_from),
+                    look_at: lerp_point(from.look_at, to.look_at),
+                    vfov: lerp(from.vfov, to.vfov),
+                    aperture: lerp(from.aperture, to.aperture),
+                    focus_distance: match (from.focus_distance, to.focus_distance) {
+                        (None, None) => None,
+                        _ => Some(lerp(from.focus(), to.focus())),
+                    },
+                    ..from
+                }
+            }
+        }
+    }
+}
+
+impl HitableDescription {
+    // whether the object is somewhere else at other times
+    fn moves(&self) -> bool {
+        match *self {
+            HitableDescription::MovingSphere { .. } => true,
+            HitableDescription::Instance { ref keyframes, .. } => !keyframes.is_empty(),
+            _ => false,
+        }
+    }
+
+    fn build(&self, shapes: &BTreeMap<String, Arc<Hitable + Sync + Send>>) -> Result<Box<Hitable + Sync + Send>, SceneFileError> {
+        Ok(match *self {
+            HitableDescription::Sphere { center, radius, ref material } => {
+                Box::new(Sphere::new(Point3::from(center), radius, material.build()))
+            }
+            HitableDescription::MovingSphere { center0, center1, movement_start, movement_duration, radius, ref material } => {
+                // the sphere's speed is its distance over this duration
+                if !(movement_duration > 0.0) {
+                    return Err(SceneFileError::Parse { line: None, column: None, message: "moving_sphere has a movement_duration which is not positive".to_string() });
+                }
+                Box::new(MovingSphere::new(Point3::from(center0), Point3::from(center1), movement_start, movement_duration, radius, material.build()))
+            }
+            HitableDescription::Plane { center, normal, ref material } => {
+                Box::new(Plane::new(Point3::from(center), Vector3::from(normal).normalize(), material.build()))
+            }
+            HitableDescription::Quad { vertices, ref rotation, ref material } => {
+                Box::new(Quad::new(
+                    [Point3::from(vertices[0]), Point3::from(vertices[1]), Point3::from(vertices[2]), Point3::from(vertices[3])],
+                    rotation.build(),
+                    material.build(),
+                ))
+            }
+            HitableDescription::Cuboid { min, max, ref rotation, ref material, ref faces } => {
+                let cuboid = faces.build_onto(Cuboid::new(Point3::from(min), Point3::from(max), material.build()));
+                let rotation = rotation.build();
+                if rotation == Quaternion::one() {
+                    Box::new(cuboid)
+                } else {
+                    Box::new(cuboid.oriented(rotation))
+                }
+            }
+            HitableDescription::Pyramid { position, base_length, height, ref rotation, ref material } => {
+                Box::new(Pyramid::new(Point3::from(position), base_length, height, rotation.build(), material.build()))
+            }
+            HitableDescription::Teapot { centre, scale, ref rotation, ref material } => {
+                Box::new(Teapot::new(Point3::from(centre), scale, rotation.build(), material.build()))
+            }
+            HitableDescription::Triangle { vertices, normals, ref material } => {
+                let vertices = [Point3::from(vertices[0]), Point3::from(vertices[1]), Point3::from(vertices[2])];
+                match normals {
+                    Some(normals) => Box::new(NormalTriangle::new(vertices, [Vector3::from(normals[0]), Vector3::from(normals[1]), Vector3::from(normals[2])], material.build())),
+                    None => Box::new(Triangle::new(vertices, material.build())),
+                }
+            }
+            HitableDescription::Mesh { ref path, centre, scale, ref rotation, ref material } => {
+                Box::new(Mesh::load_obj(path, Point3::from(centre), scale, rotation.build(), material.as_ref().map(|m| m.build()))?)
+            }
+            HitableDescription::Instance { ref shape, translation, scale, ref rotation, ref material, ref keyframes } => {
+                let hitable = shapes.get(shape).ok_or_else(|| SceneFileError::UnknownShape(shape.clone()))?;
+                let scales = keyframes.iter().map(|keyframe| keyframe.scale).chain(Some(scale));
+                if scales.flat_map(|scale| scale.to_vec()).any(|axis_scale| axis_scale == 0.0) {
+                    return Err(SceneFileError::Parse { line: None, column: None, message: format!("instance of '{}' has a zero scale", shape) });
+                }
+                if keyframes.is_empty() {
+                    let instance = Instance::new(hitable.clone(), Transform::from_parts(Vector3::from(translation), rotation.build(), Vector3::from(scale)));
+                    Box::new(match *material {
+                        Some(ref material) => instance.with_material(material.build()),
+                        None => instance,
+                    })
+                } else {
+                    let motion = AnimatedTransform::new(keyframes.iter().map(|keyframe| Keyframe {
+                        time: keyframe.time,
+                        translation: Vector3::from(keyframe.translation),
+                        rotation: keyframe.rotation.build(),
+                        scale: Vector3::from(keyframe.scale),
+                        curve: keyframe.curve,
+                    }).collect());
+                    let instance = MovingInstance::new(hitable.clone(), motion);
+                    Box::new(match *material {
+                        Some(ref material) => instance.with_material(material.build()),
+                        None => instance,
+                    })
+                }
+            }
+        })
+    }
+}
+
+impl HitableDescription {
+    // emissive spheres, quads and triangles are sampled directly as well as being hit by
+    // scattered rays, other emissive objects are only found by scattered rays
+    fn add_lights_to(&self, lights: LightList) -> LightList {
+        match *self {
+            HitableDescription::Sphere { center, radius, material: MaterialDescription::DiffuseLight { .. } } => {
+                lights.with_light(SphereLight::new(Point3::from(center), radius))
+            }
+            HitableDescription::Quad { vertices, ref rotation, material: MaterialDescription::DiffuseLight { .. } } => {
+                let rotation = rotation.build();
+                let v: Vec<Point3<f32>> = vertices.iter().map(|&vertex| rotation.rotate_point(Point3::from(vertex))).collect();
+                lights
+                    .with_light(TriangleLight::new([v[0], v[1], v[2]]))
+                    .with_light(TriangleLight::new([v[0], v[2], v[3]]))
+            }
+            HitableDescription::Triangle { vertices, material: MaterialDescription::DiffuseLight { .. }, .. } => {
+                lights.with_light(TriangleLight::new([Point3::from(vertices[0]), Point3::from(vertices[1]), Point3::from(vertices[2])]))
+            }
+            _ => lights,
+        }
+    }
+}
+
+impl MaterialDescription {
+    pub fn build(&self) -> Material {
+        match *self {
+            MaterialDescription::Metal { albedo, fuzz } => Material::Metal { albedo: Vector3::from(albedo), fuzz },
+            MaterialDescription::Lambertian { albedo } => Material::Lambertian { albedo: Vector3::from(albedo) },
+            MaterialDescription::TexturedLambertian { ref texture } => Material::TexturedLambertian { texture: texture.build() },
+            MaterialDescription::Dielectric { ref_idx } => Material::Dielectric { ref_idx },
+            MaterialDescription::DiffuseLight { ref emit, one_sided } => Material::DiffuseLight { emit: emit.build(), one_sided },
+        }
+    }
+}
+
+impl TextureDescription {
+    pub fn build(&self) -> Box<Texture + Send + Sync> {
+        match *self {
+            TextureDescription::Constant { colour } => ConstantTexture::new(Vector3::from(colour)),
+            TextureDescription::Chequered { ref odd, ref even } => ChequeredTexture::new(odd.build(), even.build()),
+            TextureDescription::Noise { scale } => NoiseTexture::new(scale),
+        }
+    }
+}
+
+impl RotationDescription {
+    pub fn about_y(angle: f32) -> Self {
+        RotationDescription { axis: [0.0, 1.0, 0.0], angle }
+    }
+
+    pub fn build(&self) -> Quaternion<f32> {
+        Quaternion::from_axis_angle(Vector3::from(self.axis).normalize(), Deg(self.angle))
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneFileError {
+    Io(io::Error),
+    Parse {
+        line: Option<usize>,
+        column: Option<usize>,
+        message: String,
+    },
+    Obj(ObjError),
+    Environment {
+        path: String,
+        message: String,
+    },
+    UnknownShape(String),
+}
+
+impl fmt::Display for SceneFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SceneFileError::Io(ref err) => write!(f, "could not read scene file: {}", err),
+            SceneFileError::Parse { line: Some(line), column: Some(column), ref message } => write!(f, "line {}, column {}: {}", line, column, message),
+            SceneFileError::Parse { ref message, .. } => write!(f, "{}", message),
+            SceneFileError::Obj(ref err) => write!(f, "could not load mesh: {}", err),
+            SceneFileError::Environment { ref path, ref message } => write!(f, "could not load environment {}: {}", path, message),
+            SceneFileError::UnknownShape(ref shape) => write!(f, "unknown shape '{}'", shape),
+        }
+    }
+}
+
+impl From<io::Error> for SceneFileError {
+    fn from(err: io::Error) -> Self {
+        SceneFileError::Io(err)
+    }
+}
+
+impl From<ObjError> for SceneFileError {
+    fn from(err: ObjError) -> Self {
+        SceneFileError::Obj(err)
+    }
+}
+
+impl From<toml::de::Error> for SceneFileError {
+    fn from(err: toml::de::Error) -> Self {
+        let line_col = err.line_col();
+        // toml reports the position of the error as part of its message, which is reported separately here
+        let message = err.to_string();
+        let message = match message.find(" at line ") {
+            Some(index) => message[..index].to_string(),
+            None => message,
+        };
+        SceneFileError::Parse {
+            line: line_col.map(|(line, _)| line + 1),
+            column: line_col.map(|(_, column)| column + 1),
+            message,
+        }
+    }
+}
+
+fn positioned_error(source: &str, header: &str, index: usize, message: String) -> SceneFileError {
+    let line = source.lines()
+        .enumerate()
+        .filter(|&(_, line)| line.trim_left().starts_with(header))
+        .nth(index)
+        .map(|(line, _)| line + 1);
+    SceneFileError::Parse {
+        line,
+        column: line.map(|_| 1),
+        message,
+    }
+}
+
+// toml cannot report positions for errors found while deserializing tagged enums,
+// so the failing table is found by deserializing each one on its own
+fn locate_error(source: &str) -> Option<SceneFileError> {
+    let document = source.parse::<toml::Value>().ok()?;
+
+    if let Some(camera) = document.get("camera") {
+        if let Err(err) = camera.clone().try_into::<CameraDescription>() {
+            return Some(positioned_error(source, "[camera]", 0, format!("camera: {}", err)));
+        }
+    }
+
+    let objects = document.get("objects").and_then(|objects| objects.as_array())?;
+    objects.iter().enumerate().filter_map(|(i, object)| {
+        object.clone().try_into::<HitableDescription>().err().map(|err| {
+            positioned_error(source, "[[objects]]", i, format!("object {}: {}", i + 1, err))
+        })
+    }).next()
+}
+
+pub fn parse(source: &str) -> Result<SceneDescription, SceneFileError> {
+    toml::from_str(source).map_err(|err: toml::de::Error| {
+        if err.line_col().is_some() {
+            err.into()
+        } else {
+            locate_error(source).unwrap_or_else(|| err.into())
+        }
+    })
+}
+
+/// Loads a scene file, resolving relative mesh and environment paths against the directory containing it.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<SceneDescription, SceneFileError> {
+    let mut source = String::new();
+    fs::File::open(path.as_ref())?.read_to_string(&mut source)?;
+    let mut description = parse(&source)?;
+
+    let directory = path.as_ref().parent().unwrap_or(Path::new(""));
+    if let EnvironmentDescription::Image { ref mut path, .. } = description.environment {
+        *path = directory.join(&path).to_string_lossy().into_owned();
+    }
+    for object in description.shapes.values_mut().chain(description.objects.iter_mut()) {
+        if let HitableDescription::Mesh { ref mut path, .. } = *object {
+            *path = directory.join(&path).to_string_lossy().into_owned();
+        }
+    }
+
+    Ok(description)
+}
+
+pub fn to_string(description: &SceneDescription) -> String {
+    toml::to_string(description).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scene;
+
+    fn assert_round_trips(description: SceneDescription) {
+        let serialized = to_string(&description);
+        assert_eq!(parse(&serialized).unwrap(), description, "\n{}", serialized);
+    }
+
+    #[test]
+    fn built_in_scenes_round_trip() {
+        assert_round_trips(scene::default_scene());
+        assert_round_trips(scene::random_scene(false));
+        assert_round_trips(scene::random_scene(true));
+        assert_round_trips(scene::teapot_scene());
+        assert_round_trips(scene::test_scene());
+        assert_round_trips(scene::two_spheres());
+        assert_round_trips(scene::two_perlin_spheres());
+        assert_round_trips(scene::cornell_box());
+    }
+
+    #[test]
+    fn example_scene_parses() {
+        let description = parse(include_str!("../scenes/example.toml")).unwrap();
+        assert_eq!(description.shapes.len(), 1);
+        assert_eq!(description.objects.len(), 8);
+    }
+
+    #[test]
+    fn cornell_scene_has_cuboids() {
+        let description = parse(include_str!("../scenes/cornell.toml")).unwrap();
+        match description.objects[7] {
+            HitableDescription::Cuboid { ref faces, .. } => assert!(faces.back.is_some() && faces.front.is_none()),
+            ref other => panic!("expected a cuboid, got {:?}", other),
+        }
+        assert_round_trips(description);
+    }
+
+    #[test]
+    fn motion_scene_round_trips() {
+        let description = parse(include_str!("../scenes/motion.toml")).unwrap();
+        match description.objects[2] {
+            HitableDescription::Instance { ref keyframes, .. } => assert_eq!(keyframes.len(), 3),
+            ref other => panic!("expected an instance, got {:?}", other),
+        }
+        assert_round_trips(description);
+    }
+
+    #[test]
+    fn camera_eases_between_keyframes() {
+        let description = parse(include_str!("../scenes/turntable.toml")).unwrap();
+        let camera = &description.camera;
+        assert_eq!(camera.at(-1.0).look_from, [0.0, 3.0, 12.0]);
+        assert_eq!(camera.at(5.0).look_from, [0.0, 3.0, -8.0]);
+        // eases in, so it has gone less than halfway by half the time
+        let halfway = camera.at(0.5);
+        assert!(halfway.look_from[0] > 0.0 && halfway.look_from[0] < 6.0, "{:?}", halfway.look_from);
+        assert_eq!(halfway.vfov, 30.0);
+        assert!(camera.at(1.5).vfov < 30.0);
+        assert_round_trips(description);
+    }
+
+    #[test]
+    fn instances_refer_to_shapes_by_name() {
+        let source = "[camera]\nlook_from = [0.0, 1.0, 2.0]\nlook_at = [0.0, 0.0, 0.0]\nvfov = 20.0\n\n\
+                      [shapes.ball]\ntype = \"sphere\"\ncenter = [0.0, 0.0, 0.0]\nradius = 1.0\nmaterial = { type = \"dielectric\", ref_idx = 1.5 }\n\n\
+                      [[objects]]\ntype = \"instance\"\nshape = \"ball\"\nscale = [1.0, 0.5, 1.0]\n\n\
+                      [[objects]]\ntype = \"instance\"\nshape = \"cube\"\n";
+        let description = parse(source).unwrap();
+        assert!(description.shapes.contains_key("ball"));
+        match description.build(1.0) {
+            Err(SceneFileError::UnknownShape(ref shape)) => assert_eq!(shape, "cube"),
+            Err(err) => panic!("expected an unknown shape, got {}", err),
+            Ok(_) => panic!("expected an unknown shape"),
+        }
+    }
+
+    #[test]
+    fn invalid_durations_are_refused() {
+        let camera = "[camera]\nlook_from = [0.0, 1.0, 2.0]\nlook_at = [0.0, 0.0, 0.0]\nvfov = 20.0\n";
+        let moving_sphere = "[[objects]]\ntype = \"moving_sphere\"\ncenter0 = [0.0, 0.0, 0.0]\ncenter1 = [0.0, 1.0, 0.0]\nradius = 1.0\n\
+                             material = { type = \"dielectric\", ref_idx = 1.5 }\n";
+        assert!(parse(&format!("{}\n{}movement_duration = 0.5\n", camera, moving_sphere)).unwrap().build(1.0).is_ok());
+        for movement_duration in ["0.0", "nan"].iter() {
+            match parse(&format!("{}\n{}movement_duration = {}\n", camera, moving_sphere, movement_duration)).unwrap().build(1.0) {
+                Err(SceneFileError::Parse { message, .. }) => assert!(message.contains("movement_duration"), "{}", message),
+                Err(err) => panic!("expected a parse error, got {}", err),
+                Ok(_) => panic!("expected a movement_duration of {} to be refused", movement_duration),
+            }
+        }
+        for shutter_duration in ["-1.0", "nan"].iter() {
+            match parse(&format!("{}shutter_duration = {}\n", camera, shutter_duration)).unwrap().build(1.0) {
+                Err(SceneFileError::Parse { message, .. }) => assert!(message.contains("shutter_duration"), "{}", message),
+                Err(err) => panic!("expected a parse error, got {}", err),
+                Ok(_) => panic!("expected a shutter_duration of {} to be refused", shutter_duration),
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_scene_reports_line_and_column() {
+        let source = "[camera]\nlook_from = [0.0, 1.0, 2.0]\nlook_at = [0.0, 0.0 0.0]\n";
+        match parse(source) {
+            Err(SceneFileError::Parse { line, column, .. }) => {
+                assert_eq!(line, Some(3));
+                assert!(column.is_some());
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_object_reports_its_table() {
+        let source = "[camera]\nlook_from = [0.0, 1.0, 2.0]\nlook_at = [0.0, 0.0, 0.0]\nvfov = 20.0\n\n\
+                      [[objects]]\ntype = \"sphere\"\ncenter = [0.0, 1.0, 0.0]\nradius = 1.0\nmaterial = { type = \"dielectric\", ref_idx = 1.5 }\n\n\
+                      [[objects]]\ntype = \"sphere\"\ncenter = [0.0, 1.0, 0.0]\nmaterial = { type = \"dielectric\", ref_idx = 1.5 }\n";
+        match parse(source) {
+            Err(SceneFileError::Parse { line, column, message }) => {
+                assert_eq!(line, Some(12));
+                assert_eq!(column, Some(1));
+                assert!(message.starts_with("object 2: missing field `radius`"), "{}", message);
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+    }
+}
@@ -4,8 +4,11 @@ use hitable::{
 };
 use ray::Ray3;
 use material::Material;
-use timeutil::TimeUtil;
 use aabb::AABB;
+use stats::{
+    self,
+    Primitive,
+};
 
 use cgmath::{
     Point3,
@@ -13,11 +16,6 @@ use cgmath::{
     InnerSpace,
 };
 
-use std::time::{
-    Duration,
-    Instant,
-};
-
 pub struct Sphere {
     center: Point3<f32>,
     radius: f32,
@@ -27,8 +25,8 @@ pub struct Sphere {
 pub struct MovingSphere {
     center1: Point3<f32>,
     center0: Point3<f32>,
-    movement_start: Instant,
-    movement_duration: Duration,
+    movement_start: f32,
+    movement_duration: f32,
     radius: f32,
     material: Material,
 }
@@ -42,8 +40,8 @@ impl Sphere {
 impl MovingSphere {
     pub fn new(center0: Point3<f32>,
                center1: Point3<f32>,
-               movement_start: Instant,
-               movement_duration: Duration,
+               movement_start: f32,
+               movement_duration: f32,
                radius: f32,
                material: Material) -> Self {
         MovingSphere {
@@ -56,15 +54,15 @@ impl MovingSphere {
         }
     }
 
-    pub fn center_at(&self, time: Instant) -> Point3<f32> {
-        let since_start = (time - self.movement_start).as_millis() as f32;
-        let movement_millis = self.movement_duration.as_millis() as f32;
-        self.center0 + (since_start / movement_millis) * (self.center1 - self.center0)
+    /// Where the sphere is at a scene time, carrying on in a straight line outside its movement.
+    pub fn center_at(&self, time: f32) -> Point3<f32> {
+        self.center0 + ((time - self.movement_start) / self.movement_duration) * (self.center1 - self.center0)
     }
 }
 
 impl Hitable for Sphere {
     fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        stats::count_hit_test(Primitive::Sphere);
         let oc = r.origin - self.center;
         let a = r.direction.dot(r.direction);
         let b = oc.dot(r.direction);
@@ -95,7 +93,7 @@ impl Hitable for Sphere {
         None
     }
 
-    fn bounding_box(&self, _: Instant, _: Instant) -> Option<AABB> {
+    fn bounding_box(&self, _: f32, _: f32) -> Option<AABB> {
         let radius_cubed = Vector3::new(self.radius, self.radius, self.radius);
         Some(AABB::new(self.center + -radius_cubed, self.center + radius_cubed))
     }
@@ -103,6 +101,7 @@ impl Hitable for Sphere {
 
 impl Hitable for MovingSphere {
     fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        stats::count_hit_test(Primitive::MovingSphere);
         let oc = r.origin - self.center_at(r.time);
         let a = r.direction.dot(r.direction);
         let b = oc.dot(r.direction);
@@ -133,10 +132,11 @@ impl Hitable for MovingSphere {
         None
     }
 
-    fn bounding_box(&self, _: Instant, _: Instant) -> Option<AABB> {
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
         let radius_cubed = Vector3::new(self.radius, self.radius, self.radius);
-        let box0 = AABB::new(self.center0 + -radius_cubed, self.center0 + radius_cubed);
-        let box1 = AABB::new(self.center1 + -radius_cubed, self.center1 + radius_cubed);
+        let (center0, center1) = (self.center_at(t0), self.center_at(t1));
+        let box0 = AABB::new(center0 + -radius_cubed, center0 + radius_cubed);
+        let box1 = AABB::new(center1 + -radius_cubed, center1 + radius_cubed);
         Some(box0.surrounding_box(&box1))
     }
 }
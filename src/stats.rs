@@ -0,0 +1,279 @@
+use std::cell::{
+    Cell,
+    RefCell,
+};
+use std::io::{
+    self,
+    Write,
+};
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+
+/// The shapes whose intersection tests are counted. Meshes, quads and the like are made of
+/// triangles and are counted as such.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Primitive {
+    Sphere,
+    MovingSphere,
+    Triangle,
+    Plane,
+    Cuboid,
+}
+
+const PRIMITIVES: [Primitive; 5] = [Primitive::Sphere, Primitive::MovingSphere, Primitive::Triangle, Primitive::Plane, Primitive::Cuboid];
+
+impl Primitive {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Primitive::Sphere => "sphere",
+            Primitive::MovingSphere => "moving_sphere",
+            Primitive::Triangle => "triangle",
+            Primitive::Plane => "plane",
+            Primitive::Cuboid => "cuboid",
+        }
+    }
+}
+
+/// Counts of the work done by a render, for comparing the cost of scenes.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RenderStats {
+    pub primary_rays: u64,
+    pub secondary_rays: u64,
+    // towards sampled lights, from diffuse surfaces
+    pub shadow_rays: u64,
+    // indexed by primitive
+    pub hit_tests: [u64; 5],
+    pub aabb_tests: u64,
+    pub bvh_nodes_visited: u64,
+    pub paths_escaped: u64,
+    pub paths_absorbed: u64,
+    pub paths_depth_capped: u64,
+    // the number of paths which ended after each number of bounces
+    pub path_depths: Vec<u64>,
+}
+
+// counters are kept per thread so that counting costs no synchronisation, and render threads
+// take theirs as they finish each tile
+struct ThreadCounters {
+    hit_tests: [Cell<u64>; 5],
+    aabb_tests: Cell<u64>,
+    bvh_nodes_visited: Cell<u64>,
+    // everything else is counted once or twice per ray
+    stats: RefCell<RenderStats>,
+}
+
+thread_local! {
+    static THREAD_COUNTERS: ThreadCounters = ThreadCounters {
+        hit_tests: [Cell::new(0), Cell::new(0), Cell::new(0), Cell::new(0), Cell::new(0)],
+        aabb_tests: Cell::new(0),
+        bvh_nodes_visited: Cell::new(0),
+        stats: RefCell::new(RenderStats::default()),
+    };
+}
+
+// intersection tests are counted so often that counting them slows rendering noticeably, so they
+// are only counted while some render wants them
+static INTERSECTION_COUNTERS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts intersection tests, AABB tests and BVH nodes visited on every thread until dropped.
+pub struct IntersectionCounting;
+
+impl IntersectionCounting {
+    pub fn start() -> Self {
+        INTERSECTION_COUNTERS.fetch_add(1, Ordering::Relaxed);
+        IntersectionCounting
+    }
+}
+
+impl Drop for IntersectionCounting {
+    fn drop(&mut self) {
+        INTERSECTION_COUNTERS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[inline]
+fn increment(counter: &Cell<u64>) {
+    counter.set(counter.get() + 1);
+}
+
+fn record<F>(count: F) where F: FnOnce(&mut RenderStats) {
+    THREAD_COUNTERS.with(|counters| count(&mut counters.stats.borrow_mut()));
+}
+
+#[inline]
+pub fn count_hit_test(primitive: Primitive) {
+    if INTERSECTION_COUNTERS.load(Ordering::Relaxed) > 0 {
+        THREAD_COUNTERS.with(|counters| increment(&counters.hit_tests[primitive as usize]));
+    }
+}
+
+#[inline]
+pub fn count_aabb_test() {
+    if INTERSECTION_COUNTERS.load(Ordering::Relaxed) > 0 {
+        THREAD_COUNTERS.with(|counters| increment(&counters.aabb_tests));
+    }
+}
+
+#[inline]
+pub fn count_bvh_node() {
+    if INTERSECTION_COUNTERS.load(Ordering::Relaxed) > 0 {
+        THREAD_COUNTERS.with(|counters| increment(&counters.bvh_nodes_visited));
+    }
+}
+
+/// Counts a ray traced `depth` bounces from the camera.
+pub fn count_ray(depth: u32) {
+    record(|stats| if depth == 0 { stats.primary_rays += 1 } else { stats.secondary_rays += 1 });
+}
+
+pub fn count_shadow_ray() {
+    record(|stats| stats.shadow_rays += 1);
+}
+
+/// How a path ended after `depth` bounces.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PathEnd {
+    Escaped,
+    Absorbed,
+    DepthCapped,
+}
+
+pub fn count_path_end(end: PathEnd, depth: u32) {
+    record(|stats| {
+        match end {
+            PathEnd::Escaped => stats.paths_escaped += 1,
+            PathEnd::Absorbed => stats.paths_absorbed += 1,
+            PathEnd::DepthCapped => stats.paths_depth_capped += 1,
+        }
+        let depth = depth as usize;
+        if stats.path_depths.len() <= depth {
+            stats.path_depths.resize(depth + 1, 0);
+        }
+        stats.path_depths[depth] += 1;
+    });
+}
+
+/// Takes the counts gathered on this thread since they were last taken.
+pub fn take_thread_stats() -> RenderStats {
+    THREAD_COUNTERS.with(|counters| {
+        let mut stats = counters.stats.replace(RenderStats::default());
+        for (tests, counter) in stats.hit_tests.iter_mut().zip(counters.hit_tests.iter()) {
+            *tests = counter.replace(0);
+        }
+        stats.aabb_tests = counters.aabb_tests.replace(0);
+        stats.bvh_nodes_visited = counters.bvh_nodes_visited.replace(0);
+        stats
+    })
+}
+
+impl RenderStats {
+    pub fn rays(&self) -> u64 {
+        self.primary_rays + self.secondary_rays + self.shadow_rays
+    }
+
+    pub fn paths(&self) -> u64 {
+        self.paths_escaped + self.paths_absorbed + self.paths_depth_capped
+    }
+
+    pub fn add(&mut self, other: &RenderStats) {
+        self.primary_rays += other.primary_rays;
+        self.secondary_rays += other.secondary_rays;
+        self.shadow_rays += other.shadow_rays;
+        for (tests, other_tests) in self.hit_tests.iter_mut().zip(other.hit_tests.iter()) {
+            *tests += *other_tests;
+        }
+        self.aabb_tests += other.aabb_tests;
+        self.bvh_nodes_visited += other.bvh_nodes_visited;
+        self.paths_escaped += other.paths_escaped;
+        self.paths_absorbed += other.paths_absorbed;
+        self.paths_depth_capped += other.paths_depth_capped;
+        if self.path_depths.len() < other.path_depths.len() {
+            self.path_depths.resize(other.path_depths.len(), 0);
+        }
+        for (paths, other_paths) in self.path_depths.iter_mut().zip(other.path_depths.iter()) {
+            *paths += *other_paths;
+        }
+    }
+
+    /// Writes a report for people, taking `seconds` to work out rates.
+    pub fn write_report<W: Write>(&self, seconds: f32, writer: &mut W) -> io::Result<()> {
+        let per_second = |count: u64| if seconds > 0f32 { count as f32 / seconds } else { 0f32 };
+        writeln!(writer, "Rays:               {:>14} ({:.0} per second)", self.rays(), per_second(self.rays()))?;
+        writeln!(writer, "  primary           {:>14}", self.primary_rays)?;
+        writeln!(writer, "  secondary         {:>14}", self.secondary_rays)?;
+        writeln!(writer, "  shadow            {:>14}", self.shadow_rays)?;
+        writeln!(writer, "Intersection tests: {:>14}", self.hit_tests.iter().sum::<u64>())?;
+        for &primitive in PRIMITIVES.iter() {
+            writeln!(writer, "  {:<17} {:>14}", primitive.name(), self.hit_tests[primitive as usize])?;
+        }
+        writeln!(writer, "AABB tests:         {:>14}", self.aabb_tests)?;
+        writeln!(writer, "BVH nodes visited:  {:>14}", self.bvh_nodes_visited)?;
+        writeln!(writer, "Paths:              {:>14}", self.paths())?;
+        writeln!(writer, "  escaped           {:>14}", self.paths_escaped)?;
+        writeln!(writer, "  absorbed          {:>14}", self.paths_absorbed)?;
+        writeln!(writer, "  depth capped      {:>14}", self.paths_depth_capped)?;
+        writeln!(writer, "Path depths:")?;
+        let most = self.path_depths.iter().cloned().max().unwrap_or(0).max(1);
+        for (depth, &paths) in self.path_depths.iter().enumerate() {
+            let bar: String = (0..(paths * 40 / most)).map(|_| '#').collect();
+            writeln!(writer, "  {:>3} {:>14} {}", depth, paths, bar)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json<W: Write>(&self, seconds: f32, writer: &mut W) -> io::Result<()> {
+        write!(writer, "{{\"seconds\":{:.3},\"rays\":{{\"primary\":{},\"secondary\":{},\"shadow\":{}}},\"hit_tests\":{{",
+               seconds, self.primary_rays, self.secondary_rays, self.shadow_rays)?;
+        for (i, &primitive) in PRIMITIVES.iter().enumerate() {
+            write!(writer, "{}\"{}\":{}", if i == 0 { "" } else { "," }, primitive.name(), self.hit_tests[primitive as usize])?;
+        }
+        write!(writer, "}},\"aabb_tests\":{},\"bvh_nodes_visited\":{},\"paths\":{{\"escaped\":{},\"absorbed\":{},\"depth_capped\":{}}},\"path_depths\":[",
+               self.aabb_tests, self.bvh_nodes_visited, self.paths_escaped, self.paths_absorbed, self.paths_depth_capped)?;
+        for (depth, paths) in self.path_depths.iter().enumerate() {
+            write!(writer, "{}{}", if depth == 0 { "" } else { "," }, paths)?;
+        }
+        writeln!(writer, "]}}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn counts_are_kept_per_thread() {
+        let _counting = IntersectionCounting::start();
+        let counted = thread::spawn(|| {
+            count_ray(0);
+            count_ray(2);
+            count_hit_test(Primitive::Triangle);
+            count_path_end(PathEnd::Absorbed, 2);
+            take_thread_stats()
+        }).join().unwrap();
+        assert_eq!((counted.primary_rays, counted.secondary_rays), (1, 1));
+        assert_eq!(counted.hit_tests, [0, 0, 1, 0, 0]);
+        assert_eq!(counted.path_depths, vec![0, 0, 1]);
+        assert_eq!(take_thread_stats(), RenderStats::default());
+    }
+
+    #[test]
+    fn adds_histograms_of_different_lengths() {
+        let mut stats = RenderStats { paths_escaped: 1, path_depths: vec![1], ..RenderStats::default() };
+        stats.add(&RenderStats { paths_absorbed: 2, path_depths: vec![0, 0, 2], ..RenderStats::default() });
+        assert_eq!(stats.paths(), 3);
+        assert_eq!(stats.path_depths, vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn writes_json() {
+        let stats = RenderStats { primary_rays: 4, hit_tests: [1, 0, 2, 0, 0], paths_escaped: 4, path_depths: vec![4], ..RenderStats::default() };
+        let mut bytes = Vec::new();
+        stats.write_json(1.5, &mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(),
+                   "{\"seconds\":1.500,\"rays\":{\"primary\":4,\"secondary\":0,\"shadow\":0},\"hit_tests\":{\"sphere\":1,\"moving_sphere\":0,\"triangle\":2,\"plane\":0,\"cuboid\":0},\"aabb_tests\":0,\"bvh_nodes_visited\":0,\"paths\":{\"escaped\":4,\"absorbed\":0,\"depth_capped\":0},\"path_depths\":[4]}\n");
+    }
+}
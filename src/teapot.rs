@@ -3,10 +3,10 @@ use triangle::NormalTriangle;
 use hitable::{
     Hitable,
     HitRecord,
-    HitableList,
 };
 use ray::Ray3;
 use aabb::AABB;
+use bvh::BVHNode;
 
 use cgmath::{
     Point3,
@@ -16,41 +16,44 @@ use cgmath::{
     Rotation,
 };
 
-use std::time::Instant;
+use std::sync::Arc;
 
 pub struct Teapot {
-    hitable_list: HitableList,
+    bvh: BVHNode,
 }
 
 impl Teapot {
     pub fn new(centre: Point3<f32>, scale: f32, rotation: Quaternion<f32>, material: Material) -> Self {
+        let triangles = (0..INDICES.len() / 3).map(|i| {
+            Arc::new(NormalTriangle::new(
+                [
+                    rotation.rotate_point(VERTICES[INDICES[i * 3 + 0]] * scale) + centre.to_vec(),
+                    rotation.rotate_point(VERTICES[INDICES[i * 3 + 1]] * scale) + centre.to_vec(),
+                    rotation.rotate_point(VERTICES[INDICES[i * 3 + 2]] * scale) + centre.to_vec(),
+                ],
+                [
+                    rotation.rotate_vector(NORMALS[INDICES[i * 3 + 0]]),
+                    rotation.rotate_vector(NORMALS[INDICES[i * 3 + 1]]),
+                    rotation.rotate_vector(NORMALS[INDICES[i * 3 + 2]]),
+                ],
+                material.clone(),
+            )) as Arc<Hitable + Send + Sync>
+        }).collect();
+
+        // triangles are static so their bounding boxes do not depend on the time interval
         Teapot {
-            hitable_list: (0..INDICES.len() / 3).fold(HitableList::new(), |hitable_list, i| {
-                hitable_list.with_hitable(NormalTriangle::new(
-                    [
-                        rotation.rotate_point(VERTICES[INDICES[i * 3 + 0]] * scale) + centre.to_vec(),
-                        rotation.rotate_point(VERTICES[INDICES[i * 3 + 1]] * scale) + centre.to_vec(),
-                        rotation.rotate_point(VERTICES[INDICES[i * 3 + 2]] * scale) + centre.to_vec(),
-                    ],
-                    [
-                        rotation.rotate_vector(NORMALS[INDICES[i * 3 + 0]]),
-                        rotation.rotate_vector(NORMALS[INDICES[i * 3 + 1]]),
-                        rotation.rotate_vector(NORMALS[INDICES[i * 3 + 2]]),
-                    ],
-                    material.clone(),
-                ))
-            })
+            bvh: BVHNode::new(triangles, 0f32, 0f32),
         }
     }
 }
 
 impl Hitable for Teapot {
     fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
-        self.hitable_list.hit(r, t_min, t_max)
+        self.bvh.hit(r, t_min, t_max)
     }
 
-    fn bounding_box(&self, t0: Instant, t1: Instant) -> Option<AABB> {
-        self.hitable_list.bounding_box(t0, t1)
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<AABB> {
+        self.bvh.bounding_box(t0, t1)
     }
 }
 
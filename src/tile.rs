@@ -0,0 +1,96 @@
+use std::sync::atomic::{
+    AtomicUsize,
+    Ordering,
+};
+
+/// A rectangle of pixels rendered as a single piece of work, measured from the top left of the image.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Hands out tiles to render threads in reading order. Threads take another tile as soon as
+/// they finish one, so those rendering cheap parts of the image take more of the work.
+pub struct TileQueue {
+    tiles: Vec<Tile>,
+    next: AtomicUsize,
+}
+
+impl TileQueue {
+    pub fn new(width: u32, height: u32, tile_size: u32) -> Self {
+        let tile_size = tile_size.max(1);
+        let mut tiles = Vec::new();
+        for row in 0..(height + tile_size - 1) / tile_size {
+            for column in 0..(width + tile_size - 1) / tile_size {
+                let (x, y) = (column * tile_size, row * tile_size);
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: tile_size.min(width - x),
+                    height: tile_size.min(height - y),
+                });
+            }
+        }
+        TileQueue {
+            tiles,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Claims the next tile to render, or `None` once every tile has been claimed.
+    pub fn take(&self) -> Option<Tile> {
+        self.tiles.get(self.next.fetch_add(1, Ordering::Relaxed)).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn tiles_cover_the_image_with_clipped_edges() {
+        let queue = TileQueue::new(40, 20, 16);
+        assert_eq!(queue.len(), 6);
+        let mut area = 0;
+        while let Some(tile) = queue.take() {
+            assert!(tile.x + tile.width <= 40 && tile.y + tile.height <= 20);
+            area += tile.width * tile.height;
+        }
+        assert_eq!(area, 40 * 20);
+        assert_eq!(queue.take(), None);
+    }
+
+    #[test]
+    fn each_tile_is_taken_once_across_threads() {
+        let queue = Arc::new(TileQueue::new(100, 100, 7));
+        let handles: Vec<_> = (0..4).map(|_| {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut taken = Vec::new();
+                while let Some(tile) = queue.take() {
+                    taken.push((tile.x, tile.y));
+                }
+                taken
+            })
+        }).collect();
+
+        let mut taken: Vec<(u32, u32)> = handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect();
+        taken.sort();
+        taken.dedup();
+        assert_eq!(taken.len(), queue.len());
+    }
+}
@@ -3,6 +3,7 @@ use std::time::Duration;
 pub trait TimeUtil {
     fn as_millis(&self) -> u64;
     fn as_nanos(&self) -> u64;
+    fn as_seconds(&self) -> f32;
     fn mul_decimal(&self, k: f32) -> Duration;
 }
 
@@ -15,6 +16,10 @@ impl TimeUtil for Duration {
         return self.as_secs() * 1_000_000_000 + self.subsec_nanos() as u64;
     }
 
+    fn as_seconds(&self) -> f32 {
+        self.as_secs() as f32 + self.subsec_nanos() as f32 / 1_000_000_000f32
+    }
+
     fn mul_decimal(&self, k: f32) -> Duration {
         Duration::from_millis((self.as_millis() as f32 * k) as u64)
     }
@@ -44,6 +49,11 @@ mod tests {
         assert_eq!(Duration::from_millis(12).as_nanos(), 12000000);
     }
 
+    #[test]
+    fn as_seconds_returns_fractional_seconds() {
+        assert_eq!(Duration::from_millis(2250).as_seconds(), 2.25);
+    }
+
     #[test]
     fn mul_decimal_provides_super_second_results() {
         assert_eq!(Duration::from_millis(500).mul_decimal(2.5).as_millis(), 1250);
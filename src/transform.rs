@@ -0,0 +1,296 @@
+use ray::Ray3;
+use aabb::AABB;
+use animation::Curve;
+
+use cgmath::{
+    InnerSpace,
+    Matrix,
+    Matrix4,
+    Point3,
+    Quaternion,
+    SquareMatrix,
+    Vector3,
+};
+
+use std::cmp::Ordering;
+
+/// An affine transform from object space to world space, kept along with its inverse so that
+/// rays can be taken the other way.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Transform {
+    matrix: Matrix4<f32>,
+    inverse: Matrix4<f32>,
+}
+
+impl Transform {
+    pub fn identity() -> Self {
+        Transform {
+            matrix: Matrix4::identity(),
+            inverse: Matrix4::identity(),
+        }
+    }
+
+    /// The transform for a matrix, or `None` if it cannot be undone, such as a scale by zero.
+    pub fn from_matrix(matrix: Matrix4<f32>) -> Option<Self> {
+        matrix.invert().map(|inverse| Transform { matrix, inverse })
+    }
+
+    pub fn translation(offset: Vector3<f32>) -> Self {
+        Transform {
+            matrix: Matrix4::from_translation(offset),
+            inverse: Matrix4::from_translation(-offset),
+        }
+    }
+
+    pub fn rotation(rotation: Quaternion<f32>) -> Self {
+        Transform {
+            matrix: Matrix4::from(rotation),
+            inverse: Matrix4::from(rotation).transpose(),
+        }
+    }
+
+    /// A scale along each axis, none of which may be zero.
+    pub fn scale(x: f32, y: f32, z: f32) -> Self {
+        Transform {
+            matrix: Matrix4::from_nonuniform_scale(x, y, z),
+            inverse: Matrix4::from_nonuniform_scale(1f32 / x, 1f32 / y, 1f32 / z),
+        }
+    }
+
+    /// Scales, then rotates, then translates, which is the usual order for placing an object.
+    pub fn from_parts(translation: Vector3<f32>, rotation: Quaternion<f32>, scale: Vector3<f32>) -> Self {
+        Transform::scale(scale.x, scale.y, scale.z)
+            .then(&Transform::rotation(rotation))
+            .then(&Transform::translation(translation))
+    }
+
+    /// This transform followed by another.
+    pub fn then(&self, other: &Transform) -> Self {
+        Transform {
+            matrix: other.matrix * self.matrix,
+            inverse: self.inverse * other.inverse,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Transform {
+            matrix: self.inverse,
+            inverse: self.matrix,
+        }
+    }
+
+    pub fn point(&self, point: Point3<f32>) -> Point3<f32> {
+        Point3::from_homogeneous(self.matrix * point.to_homogeneous())
+    }
+
+    pub fn vector(&self, vector: Vector3<f32>) -> Vector3<f32> {
+        (self.matrix * vector.extend(0f32)).truncate()
+    }
+
+    /// Normals stay perpendicular to surfaces by transforming with the inverse transpose. The
+    /// result is not normalised.
+    pub fn normal(&self, normal: Vector3<f32>) -> Vector3<f32> {
+        (self.inverse.transpose() * normal.extend(0f32)).truncate()
+    }
+
+    /// Takes a world space ray into object space. The direction is not normalised, so distances
+    /// along the ray are the same in both spaces.
+    pub fn ray_to_object(&self, ray: &Ray3<f32>) -> Ray3<f32> {
+        let inverse = self.inverse();
+        Ray3::new(inverse.point(ray.origin), inverse.vector(ray.direction), ray.time)
+    }
+
+    /// The world space box around an object space box, found from its eight corners.
+    pub fn bounding_box(&self, bounding_box: &AABB) -> AABB {
+        let corner = |i: usize| Point3::new(
+            if i & 1 == 0 { bounding_box.min.x } else { bounding_box.max.x },
+            if i & 2 == 0 { bounding_box.min.y } else { bounding_box.max.y },
+            if i & 4 == 0 { bounding_box.min.z } else { bounding_box.max.z },
+        );
+        let first = self.point(corner(0));
+        (1..8).map(|i| self.point(corner(i))).fold(AABB::new(first, first), |acc, p| acc.surrounding_box(&AABB::new(p, p)))
+    }
+}
+
+/// Where an object is at a moment of scene time, in seconds, as in `Transform::from_parts`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Keyframe {
+    pub time: f32,
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+    // how the placement eases towards the next keyframe
+    pub curve: Curve,
+}
+
+// the steps each pair of keyframes is split into when bounding the motion between them
+const BOUNDING_STEPS: usize = 16;
+
+/// A transform which moves through keyframes over time, holding still before the first and after
+/// the last. Translation and scale are interpolated linearly and rotation spherically along each
+/// keyframe's curve, turning through the angle between each pair of keyframes as given, so there
+/// must be less than a full turn between them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AnimatedTransform {
+    keyframes: Vec<Keyframe>,
+}
+
+impl AnimatedTransform {
+    /// Panics if there are no keyframes.
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Self {
+        assert!(!keyframes.is_empty(), "an animated transform needs at least one keyframe");
+        keyframes.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+        AnimatedTransform { keyframes }
+    }
+
+    pub fn at(&self, time: f32) -> Transform {
+        match self.keyframes.iter().position(|keyframe| keyframe.time > time) {
+            Some(0) => from_keyframe(&self.keyframes[0]),
+            Some(next) => {
+                let (from, to) = (&self.keyframes[next - 1], &self.keyframes[next]);
+                from_keyframe(&interpolate(from, to, amount_at(from, to, time)))
+            }
+            None => from_keyframe(&self.keyframes[self.keyframes.len() - 1]),
+        }
+    }
+
+    /// The world space box around an object space box over the whole of the motion between `t0`
+    /// and `t1`. The motion is bounded in steps, each widened by how far a rotating corner can
+    /// bulge out between its ends.
+    pub fn bounding_box(&self, bounding_box: &AABB, t0: f32, t1: f32) -> AABB {
+        let mut motion_box = self.at(t0).bounding_box(bounding_box).surrounding_box(&self.at(t1).bounding_box(bounding_box));
+        let furthest = |min: f32, max: f32| min.abs().max(max.abs());
+        let corner_distance = Vector3::new(
+            furthest(bounding_box.min.x, bounding_box.max.x),
+            furthest(bounding_box.min.y, bounding_box.max.y),
+            furthest(bounding_box.min.z, bounding_box.max.z),
+        ).magnitude();
+        let largest_scale = |scale: Vector3<f32>| scale.x.abs().max(scale.y.abs()).max(scale.z.abs());
+        for pair in self.keyframes.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            if to.time <= t0 || from.time >= t1 {
+                continue;
+            }
+            let (start, end) = (amount_at(from, to, t0), amount_at(from, to, t1));
+            let radius = corner_distance * largest_scale(from.scale).max(largest_scale(to.scale));
+            let mut previous = interpolate(from, to, start);
+            for step in 1..(BOUNDING_STEPS + 1) {
+                let keyframe = interpolate(from, to, start + (end - start) * step as f32 / BOUNDING_STEPS as f32);
+                let step_angle = 2f32 * previous.rotation.dot(keyframe.rotation).abs().min(1f32).acos();
+                let bulge = radius * (1f32 - (step_angle / 2f32).cos());
+                let padding = Vector3::new(bulge, bulge, bulge);
+                let step_box = from_keyframe(&previous).bounding_box(bounding_box)
+                    .surrounding_box(&from_keyframe(&keyframe).bounding_box(bounding_box));
+                motion_box = motion_box.surrounding_box(&AABB::new(step_box.min + -padding, step_box.max + padding));
+                previous = keyframe;
+            }
+        }
+        motion_box
+    }
+}
+
+// how far `time` is from one keyframe to the next, held between 0 and 1
+fn amount_at(from: &Keyframe, to: &Keyframe, time: f32) -> f32 {
+    if time <= from.time {
+        0f32
+    } else if time >= to.time {
+        1f32
+    } else {
+        (time - from.time) / (to.time - from.time)
+    }
+}
+
+fn from_keyframe(keyframe: &Keyframe) -> Transform {
+    Transform::from_parts(keyframe.translation, keyframe.rotation, keyframe.scale)
+}
+
+// only the placement is interpolated, the time and curve being left as the first keyframe's
+fn interpolate(from: &Keyframe, to: &Keyframe, amount: f32) -> Keyframe {
+    let amount = from.curve.apply(amount);
+    Keyframe {
+        time: from.time,
+        curve: from.curve,
+        translation: from.translation.lerp(to.translation, amount),
+        rotation: from.rotation.slerp(to.rotation, amount),
+        scale: from.scale.lerp(to.scale, amount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cgmath::{
+        Deg,
+        EuclideanSpace,
+        One,
+        Rotation3,
+    };
+
+    fn assert_near(a: Vector3<f32>, b: Vector3<f32>) {
+        assert!((a - b).magnitude() < 0.0001, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn scales_then_rotates_then_translates() {
+        let transform = Transform::from_parts(Vector3::new(1.0, 2.0, 3.0), Quaternion::from_angle_y(Deg(90.0)), Vector3::new(2.0, 1.0, 1.0));
+        let p = transform.point(Point3::new(1.0, 0.0, 0.0));
+        assert_near(p.to_vec(), Vector3::new(1.0, 2.0, 1.0));
+        assert_near(transform.inverse().point(p).to_vec(), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let transform = Transform::scale(4.0, 1.0, 1.0);
+        // the surface x + y = 1 becomes x / 4 + y = 1
+        let tangent = transform.vector(Vector3::new(1.0, -1.0, 0.0));
+        let normal = transform.normal(Vector3::new(1.0, 1.0, 0.0));
+        assert!(tangent.dot(normal).abs() < 0.0001);
+    }
+
+    #[test]
+    fn bounding_boxes_enclose_rotated_corners() {
+        let transform = Transform::rotation(Quaternion::from_angle_z(Deg(45.0)));
+        let bounding_box = transform.bounding_box(&AABB::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0)));
+        let half_diagonal = 2f32.sqrt();
+        assert_near(bounding_box.min.to_vec(), Vector3::new(-half_diagonal, -half_diagonal, -1.0));
+        assert_near(bounding_box.max.to_vec(), Vector3::new(half_diagonal, half_diagonal, 1.0));
+    }
+
+    #[test]
+    fn singular_matrices_are_refused() {
+        assert!(Transform::from_matrix(Matrix4::from_nonuniform_scale(1.0, 0.0, 1.0)).is_none());
+    }
+
+    fn turning_keyframes() -> AnimatedTransform {
+        AnimatedTransform::new(vec![
+            Keyframe { time: 1.0, translation: Vector3::new(4.0, 0.0, 0.0), rotation: Quaternion::from_angle_y(Deg(90.0)), scale: Vector3::new(1.0, 1.0, 1.0), curve: Curve::Linear },
+            Keyframe { time: 0.0, translation: Vector3::new(0.0, 0.0, 0.0), rotation: Quaternion::one(), scale: Vector3::new(1.0, 1.0, 1.0), curve: Curve::Linear },
+        ])
+    }
+
+    #[test]
+    fn interpolates_between_keyframes_and_holds_beyond_them() {
+        let motion = turning_keyframes();
+        let p = Point3::new(1.0, 0.0, 0.0);
+        let halfway = (Quaternion::from_angle_y(Deg(45.0)) * Vector3::new(1.0, 0.0, 0.0)) + Vector3::new(2.0, 0.0, 0.0);
+        assert_near(motion.at(0.5).point(p).to_vec(), halfway);
+        assert_near(motion.at(-1.0).point(p).to_vec(), Vector3::new(1.0, 0.0, 0.0));
+        assert_near(motion.at(5.0).point(p).to_vec(), Vector3::new(4.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn bounding_boxes_cover_the_motion_during_the_shutter() {
+        let motion = turning_keyframes();
+        let object_box = AABB::new(Point3::new(1.0, -0.5, -0.5), Point3::new(2.0, 0.5, 0.5));
+        let motion_box = motion.bounding_box(&object_box, 0.2, 0.7);
+        for step in 0..501 {
+            let time = 0.2 + step as f32 * 0.001;
+            let moved = motion.at(time).bounding_box(&object_box);
+            let covering = motion_box.surrounding_box(&moved);
+            assert!(covering.min == motion_box.min && covering.max == motion_box.max, "not covered at {}", time);
+        }
+        let whole_motion = motion.bounding_box(&object_box, 0.0, 1.0);
+        assert!(motion_box.max.x < whole_motion.max.x);
+    }
+}
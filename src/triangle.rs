@@ -5,18 +5,33 @@ use hitable::{
 use ray::Ray3;
 use aabb::AABB;
 use material::Material;
+use stats::{
+    self,
+    Primitive,
+};
 
 use cgmath::{
     Point3,
+    Vector2,
     Vector3,
     InnerSpace,
 };
 
-use std::time::Instant;
+// texture coordinates which map the barycentric coordinates of a hit straight onto u and v
+const DEFAULT_UVS: [Vector2<f32>; 3] = [
+    Vector2 { x: 0.0, y: 0.0 },
+    Vector2 { x: 1.0, y: 0.0 },
+    Vector2 { x: 0.0, y: 1.0 },
+];
+
+fn interpolate_uv(uvs: &[Vector2<f32>; 3], u: f32, v: f32) -> Vector2<f32> {
+    (1f32 - u - v) * uvs[0] + u * uvs[1] + v * uvs[2]
+}
 
 pub struct Triangle {
     vertices: [Point3<f32>; 3],
     surface_normal: Vector3<f32>,
+    uvs: [Vector2<f32>; 3],
     material: Material,
 }
 
@@ -25,12 +40,18 @@ impl Triangle {
         let e1 = vertices[1] - vertices[0];
         let e2 = vertices[2] - vertices[0];
         let surface_normal = e2.cross(e1).normalize();
-        Self { vertices, surface_normal, material }
+        Self { vertices, surface_normal, uvs: DEFAULT_UVS, material }
+    }
+
+    pub fn with_uvs(mut self, uvs: [Vector2<f32>; 3]) -> Self {
+        self.uvs = uvs;
+        self
     }
 }
 
 impl Hitable for Triangle {
     fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        stats::count_hit_test(Primitive::Triangle);
         let e1 = self.vertices[1] - self.vertices[0];
         let e2 = self.vertices[2] - self.vertices[0];
         let dir = r.direction;
@@ -60,18 +81,19 @@ impl Hitable for Triangle {
         let t = e2.dot(qvec) * inv_det;
 
         if t > 0.00001 && t < t_max && t > t_min {
+            let uv = interpolate_uv(&self.uvs, u, v);
             return Some(HitRecord::new(
                 t,
                 r.point_at_parameter(t),
                 self.surface_normal,
                 Some(self.material.clone()),
-            ));
+            ).with_uv(uv.x, uv.y));
         }
 
         None
     }
 
-    fn bounding_box(&self, _: Instant, _: Instant) -> Option<AABB> {
+    fn bounding_box(&self, _: f32, _: f32) -> Option<AABB> {
         let mut min = Point3::new(self.vertices[0].x.min(self.vertices[1].x), self.vertices[0].y.min(self.vertices[1].y), self.vertices[0].z.min(self.vertices[1].z));
         min = Point3::new(min.x.min(self.vertices[2].x), min.y.min(self.vertices[2].y), min.z.min(self.vertices[2].z));
 
@@ -85,17 +107,24 @@ impl Hitable for Triangle {
 pub struct NormalTriangle {
     vertices: [Point3<f32>; 3],
     normals: [Vector3<f32>; 3],
+    uvs: [Vector2<f32>; 3],
     material: Material,
 }
 
 impl NormalTriangle {
     pub fn new(vertices: [Point3<f32>; 3], normals: [Vector3<f32>; 3], material: Material) -> Self {
-        Self { vertices, normals, material }
+        Self { vertices, normals, uvs: DEFAULT_UVS, material }
+    }
+
+    pub fn with_uvs(mut self, uvs: [Vector2<f32>; 3]) -> Self {
+        self.uvs = uvs;
+        self
     }
 }
 
 impl Hitable for NormalTriangle {
     fn hit(&self, r: &Ray3<f32>, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        stats::count_hit_test(Primitive::Triangle);
         let e1 = self.vertices[1] - self.vertices[0];
         let e2 = self.vertices[2] - self.vertices[0];
         let dir = r.direction;
@@ -127,18 +156,19 @@ impl Hitable for NormalTriangle {
         let p = r.point_at_parameter(t);
         if t > 0.00001 && t < t_max && t > t_min {
             let normal = u * self.normals[1] + v * self.normals[2] + (1f32 - u - v) * self.normals[0];
+            let uv = interpolate_uv(&self.uvs, u, v);
             Some(HitRecord::new(
                 t,
                 p,
                 normal,
                 Some(self.material.clone()),
-            ))
+            ).with_uv(uv.x, uv.y))
         } else {
             None
         }
     }
 
-    fn bounding_box(&self, _: Instant, _: Instant) -> Option<AABB> {
+    fn bounding_box(&self, _: f32, _: f32) -> Option<AABB> {
         let mut min = Point3::new(self.vertices[0].x.min(self.vertices[1].x), self.vertices[0].y.min(self.vertices[1].y), self.vertices[0].z.min(self.vertices[1].z));
         min = Point3::new(min.x.min(self.vertices[2].x), min.y.min(self.vertices[2].y), min.z.min(self.vertices[2].z));
 